@@ -11,7 +11,7 @@ _gitfetch_completions() {
 
   # If we're still at the command level
   if [ "$cword" -eq 1 ]; then
-    local commands="clone -c list -l search -s easter-egg -e completions checksum verify help --help -h --version -V"
+    local commands="clone -c list -l search -s easter-egg -e completions checksum verify update remove uninstall lock sync rollback outdated info gc doctor adopt release auth help --help -h --version -V"
     COMPREPLY=($(compgen -W "$commands" -- "$cur"))
     return 0
   fi
@@ -19,6 +19,27 @@ _gitfetch_completions() {
   # Handle completions based on the subcommand
   case "$cmd" in
   clone | -c)
+    # Refs are free-form (tag, branch or commit)
+    if [[ "$prev" == "--ref" || "$prev" == "--depth" || "$prev" == "--sparse" || "$prev" == "--lfs-max-size" || "$prev" == "--jobs" || "$prev" == "-j" ]]; then
+      COMPREPLY=()
+      return 0
+    fi
+
+    if [[ "$prev" == "--filter" ]]; then
+      COMPREPLY=($(compgen -W "blob:none tree:0" -- "$cur"))
+      return 0
+    fi
+
+    if [[ "$prev" == "--bundle" ]]; then
+      _filedir
+      return 0
+    fi
+
+    if [[ "$prev" == "--submodules" ]]; then
+      COMPREPLY=($(compgen -W "none prompt recursive" -- "$cur"))
+      return 0
+    fi
+
     # CRITICAL: Check if previous word is --trust-mode FIRST
     if [[ "$prev" == "--trust-mode" ]]; then
       # Complete trust mode values
@@ -29,7 +50,7 @@ _gitfetch_completions() {
 
     # Check if we're completing a flag
     if [[ "$cur" == -* ]]; then
-      local flags="--verify-checksum -v --trust-mode"
+      local flags="--verify-checksum -v --trust-mode --ref --depth --filter --sparse --submodules --lfs --lfs-max-size --jobs -j --offline --bundle --archive"
       COMPREPLY=($(compgen -W "$flags" -- "$cur"))
       return 0
     fi
@@ -58,12 +79,134 @@ _gitfetch_completions() {
     fi
     ;;
   verify)
-    if [ "$cword" -eq 2 ]; then
+    if [[ "$prev" == "--jobs" || "$prev" == "-j" ]]; then
+      COMPREPLY=()
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--all -a --jobs -j" -- "$cur"))
+    else
       # Suggest directories (repository paths)
       _filedir -d
     fi
     ;;
-  list | -l | easter-egg | -e | help)
+  update)
+    if [[ "$prev" == "--trust-mode" ]]; then
+      local modes="paranoid normal yolo"
+      COMPREPLY=($(compgen -W "$modes" -- "$cur"))
+      return 0
+    fi
+
+    if [[ "$prev" == "--jobs" || "$prev" == "-j" ]]; then
+      COMPREPLY=()
+      return 0
+    fi
+
+    if [[ "$cur" == -* ]]; then
+      local flags="--all -a --jobs -j --trust-mode"
+      COMPREPLY=($(compgen -W "$flags" -- "$cur"))
+      return 0
+    fi
+
+    local suggestions=$(gitfetch complete repos "$cur" 2>/dev/null)
+    COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    ;;
+  remove | uninstall)
+    if [[ "$cur" == -* ]]; then
+      local flags="--forget-checksum --yes -y"
+      COMPREPLY=($(compgen -W "$flags" -- "$cur"))
+      return 0
+    fi
+
+    local suggestions=$(gitfetch complete repos "$cur" 2>/dev/null)
+    COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    ;;
+  lock)
+    if [ "$cword" -eq 2 ]; then
+      COMPREPLY=($(compgen -W "export install" -- "$cur"))
+    elif [[ "$prev" == "--trust-mode" ]]; then
+      COMPREPLY=($(compgen -W "paranoid normal yolo" -- "$cur"))
+    elif [[ "$prev" == "--output" || "$prev" == "-o" || "$prev" == "--file" || "$prev" == "-f" ]]; then
+      _filedir
+    elif [[ "$cur" == -* ]]; then
+      if [[ "${words[2]}" == "export" ]]; then
+        COMPREPLY=($(compgen -W "--output -o" -- "$cur"))
+      else
+        COMPREPLY=($(compgen -W "--file -f --trust-mode" -- "$cur"))
+      fi
+    fi
+    ;;
+  sync)
+    if [[ "$prev" == "--file" || "$prev" == "-f" ]]; then
+      _filedir toml
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--file -f --update -u" -- "$cur"))
+    fi
+    ;;
+  rollback)
+    if [[ "$prev" == "--to" ]]; then
+      COMPREPLY=()
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--to --list" -- "$cur"))
+    elif [ "$cword" -eq 2 ]; then
+      local suggestions=$(gitfetch complete repos "$cur" 2>/dev/null)
+      COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    fi
+    ;;
+  info)
+    if [ "$cword" -eq 2 ]; then
+      local suggestions=$(gitfetch complete repos "$cur" 2>/dev/null)
+      COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    fi
+    ;;
+  gc)
+    if [[ "$prev" == "--max-size" ]]; then
+      COMPREPLY=()
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--dry-run -n --max-size --yes -y" -- "$cur"))
+    fi
+    ;;
+  release)
+    if [[ "$prev" == "--trust-mode" ]]; then
+      COMPREPLY=($(compgen -W "paranoid normal yolo" -- "$cur"))
+    elif [[ "$prev" == "--output" || "$prev" == "-o" ]]; then
+      COMPREPLY=($(compgen -d -- "$cur"))
+    elif [[ "$prev" == "--tag" || "$prev" == "--asset" ]]; then
+      COMPREPLY=()
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--tag --asset --output -o --trust-mode" -- "$cur"))
+    fi
+    ;;
+  auth)
+    if [ "$cword" -eq 2 ]; then
+      COMPREPLY=($(compgen -W "login logout status" -- "$cur"))
+    elif [[ "$cur" == -* ]]; then
+      if [[ "${words[2]}" == "login" ]]; then
+        COMPREPLY=($(compgen -W "--git-credential" -- "$cur"))
+      fi
+    elif [[ "${words[2]}" == "login" || "${words[2]}" == "logout" ]]; then
+      local suggestions=$(gitfetch complete forges "$cur" 2>/dev/null)
+      COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    fi
+    ;;
+  adopt)
+    if [[ "$prev" == "--trust-mode" ]]; then
+      COMPREPLY=($(compgen -W "paranoid normal yolo" -- "$cur"))
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--trust-mode" -- "$cur"))
+    else
+      COMPREPLY=($(compgen -d -- "$cur"))
+    fi
+    ;;
+  outdated)
+    if [[ "$prev" == "--jobs" || "$prev" == "-j" ]]; then
+      COMPREPLY=()
+    elif [[ "$cur" == -* ]]; then
+      COMPREPLY=($(compgen -W "--jobs -j --json" -- "$cur"))
+    else
+      local suggestions=$(gitfetch complete repos "$cur" 2>/dev/null)
+      COMPREPLY=($(compgen -W "$suggestions" -- "$cur"))
+    fi
+    ;;
+  list | -l | easter-egg | -e | doctor | help)
     # These commands take no arguments
     COMPREPLY=()
     ;;
@@ -16,6 +16,19 @@ complete -c gitfetch -n __fish_use_subcommand -a easter-egg -d "Print something
 complete -c gitfetch -n __fish_use_subcommand -a completions -d "Generate shell completion scripts"
 complete -c gitfetch -n __fish_use_subcommand -a checksum -d "Calculate checksums for a repository"
 complete -c gitfetch -n __fish_use_subcommand -a verify -d "Verify repository integrity"
+complete -c gitfetch -n __fish_use_subcommand -a update -d "Update installed repositories"
+complete -c gitfetch -n __fish_use_subcommand -a remove -d "Uninstall a repository"
+complete -c gitfetch -n __fish_use_subcommand -a uninstall -d "Uninstall a repository"
+complete -c gitfetch -n __fish_use_subcommand -a lock -d "Export or reproduce installed repos"
+complete -c gitfetch -n __fish_use_subcommand -a sync -d "Sync repos declared in gitfetch.toml"
+complete -c gitfetch -n __fish_use_subcommand -a rollback -d "Restore a previously installed version"
+complete -c gitfetch -n __fish_use_subcommand -a outdated -d "Show installed repos whose remote moved"
+complete -c gitfetch -n __fish_use_subcommand -a info -d "Show details about an installed repository"
+complete -c gitfetch -n __fish_use_subcommand -a gc -d "Delete orphaned or oversized workspaces"
+complete -c gitfetch -n __fish_use_subcommand -a doctor -d "Check the sandbox and gitfetch state"
+complete -c gitfetch -n __fish_use_subcommand -a adopt -d "Manage an existing local clone"
+complete -c gitfetch -n __fish_use_subcommand -a release -d "Download and verify a release asset"
+complete -c gitfetch -n __fish_use_subcommand -a auth -d "Save, remove or show API tokens"
 complete -c gitfetch -n __fish_use_subcommand -a help -d "Print help message"
 complete -c gitfetch -n __fish_use_subcommand -s h -l help -d "Show help"
 complete -c gitfetch -n __fish_use_subcommand -s V -l version -d "Show version"
@@ -23,9 +36,20 @@ complete -c gitfetch -n __fish_use_subcommand -s V -l version -d "Show version"
 # Clone command options
 complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -s v -l verify-checksum -d "Verify against known checksums"
 complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l trust-mode -d "Trust mode" -x -a "paranoid normal yolo"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l ref -d "Tag, branch or commit to check out" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l depth -d "Shallow clone with this many commits" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l filter -d "Partial clone filter" -x -a "blob:none tree:0"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l sparse -d "Only check out these directories" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l submodules -d "Submodule policy" -x -a "none prompt recursive"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l lfs -d "Download Git LFS content"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l lfs-max-size -d "Skip LFS files larger than this many MB" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -s j -l jobs -d "How many repositories to clone at once" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l offline -d "Clone from the local cache without network"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l bundle -d "Git bundle to clone from when offline" -r -F
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c" -l archive -d "Download a source tarball instead of cloning"
 
 # Clone repository suggestions (dynamic)
-complete -c gitfetch -n "__fish_seen_subcommand_from clone -c; and not __fish_seen_subcommand_from --verify-checksum -v --trust-mode" -a "(gitfetch complete clone-targets (commandline -ct) 2>/dev/null)"
+complete -c gitfetch -n "__fish_seen_subcommand_from clone -c; and not __fish_seen_subcommand_from --verify-checksum -v --trust-mode --ref --depth --filter --sparse --submodules --lfs --lfs-max-size --jobs -j --offline --bundle --archive" -a "(gitfetch complete clone-targets (commandline -ct) 2>/dev/null)"
 
 # Checksum command options
 complete -c gitfetch -n "__fish_seen_subcommand_from checksum" -s s -l save -d "Save checksum to registry"
@@ -33,6 +57,65 @@ complete -c gitfetch -n "__fish_seen_subcommand_from checksum" -F -d "Repository
 
 # Verify command
 complete -c gitfetch -n "__fish_seen_subcommand_from verify" -F -d "Repository path"
+complete -c gitfetch -n "__fish_seen_subcommand_from verify" -s a -l all -d "Verify every installed repository"
+complete -c gitfetch -n "__fish_seen_subcommand_from verify" -s j -l jobs -d "How many repositories to verify at once" -x
+
+# Update command
+complete -c gitfetch -n "__fish_seen_subcommand_from update" -s a -l all -d "Update every installed repository"
+complete -c gitfetch -n "__fish_seen_subcommand_from update" -s j -l jobs -d "How many repositories to update at once" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from update" -l trust-mode -d "Trust mode" -x -a "paranoid normal yolo"
+complete -c gitfetch -n "__fish_seen_subcommand_from update" -a "(gitfetch complete repos (commandline -ct) 2>/dev/null)"
+
+# Remove command
+complete -c gitfetch -n "__fish_seen_subcommand_from remove uninstall" -l forget-checksum -d "Also drop the checksum registry entry"
+complete -c gitfetch -n "__fish_seen_subcommand_from remove uninstall" -s y -l yes -d "Do not ask for confirmation"
+complete -c gitfetch -n "__fish_seen_subcommand_from remove uninstall" -a "(gitfetch complete repos (commandline -ct) 2>/dev/null)"
+
+# Lock command
+complete -c gitfetch -n "__fish_seen_subcommand_from lock; and not __fish_seen_subcommand_from export install" -a export -d "Write a lockfile"
+complete -c gitfetch -n "__fish_seen_subcommand_from lock; and not __fish_seen_subcommand_from export install" -a install -d "Install from a lockfile"
+complete -c gitfetch -n "__fish_seen_subcommand_from lock; and __fish_seen_subcommand_from export" -s o -l output -d "Lockfile to write" -r -F
+complete -c gitfetch -n "__fish_seen_subcommand_from lock; and __fish_seen_subcommand_from install" -s f -l file -d "Lockfile to read" -r -F
+complete -c gitfetch -n "__fish_seen_subcommand_from lock; and __fish_seen_subcommand_from install" -l trust-mode -d "Trust mode" -x -a "paranoid normal yolo"
+
+# Sync command
+complete -c gitfetch -n "__fish_seen_subcommand_from sync" -s f -l file -d "Manifest to read" -r -F
+complete -c gitfetch -n "__fish_seen_subcommand_from sync" -s u -l update -d "Also fetch upstream changes"
+
+# Rollback command
+complete -c gitfetch -n "__fish_seen_subcommand_from rollback" -l to -d "Commit to restore" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from rollback" -l list -d "Show saved versions"
+complete -c gitfetch -n "__fish_seen_subcommand_from rollback" -a "(gitfetch complete repos (commandline -ct) 2>/dev/null)"
+
+# Outdated command
+complete -c gitfetch -n "__fish_seen_subcommand_from outdated" -l json -d "Print results as JSON"
+complete -c gitfetch -n "__fish_seen_subcommand_from outdated" -s j -l jobs -d "How many remotes to query at once" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from outdated" -a "(gitfetch complete repos (commandline -ct) 2>/dev/null)"
+
+# Info command
+complete -c gitfetch -n "__fish_seen_subcommand_from info" -a "(gitfetch complete repos (commandline -ct) 2>/dev/null)"
+
+# GC command
+complete -c gitfetch -n "__fish_seen_subcommand_from gc" -s n -l dry-run -d "Only list what would be deleted"
+complete -c gitfetch -n "__fish_seen_subcommand_from gc" -l max-size -d "Also delete workspaces larger than this many MB" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from gc" -s y -l yes -d "Do not ask for confirmation"
+
+# Adopt command
+complete -c gitfetch -n "__fish_seen_subcommand_from adopt" -l trust-mode -d "Trust mode" -x -a "paranoid normal yolo"
+complete -c gitfetch -n "__fish_seen_subcommand_from adopt" -a "(__fish_complete_directories)" -d "Existing clone"
+
+# Release command
+complete -c gitfetch -n "__fish_seen_subcommand_from release" -l tag -d "Release tag" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from release" -l asset -d "Asset name, * matches anything" -x
+complete -c gitfetch -n "__fish_seen_subcommand_from release" -s o -l output -d "Directory to put the asset in" -x -a "(__fish_complete_directories)"
+complete -c gitfetch -n "__fish_seen_subcommand_from release" -l trust-mode -d "Trust mode" -x -a "paranoid normal yolo"
+
+# Auth command
+complete -c gitfetch -n "__fish_seen_subcommand_from auth; and not __fish_seen_subcommand_from login logout status" -a login -d "Save a token for a forge"
+complete -c gitfetch -n "__fish_seen_subcommand_from auth; and not __fish_seen_subcommand_from login logout status" -a logout -d "Forget a forge's token"
+complete -c gitfetch -n "__fish_seen_subcommand_from auth; and not __fish_seen_subcommand_from login logout status" -a status -d "Show where tokens come from"
+complete -c gitfetch -n "__fish_seen_subcommand_from auth; and __fish_seen_subcommand_from login logout" -x -a "(gitfetch complete forges (commandline -ct) 2>/dev/null)" -d "Forge"
+complete -c gitfetch -n "__fish_seen_subcommand_from auth; and __fish_seen_subcommand_from login" -l git-credential -d "Use the git credential helper"
 
 # Completions command - shell types
 complete -c gitfetch -n "__fish_seen_subcommand_from completions" -a "bash zsh fish powershell elvish" -d "Shell type"
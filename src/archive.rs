@@ -0,0 +1,276 @@
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+
+/// Records where a snapshot came from. Hidden, so checksums and scans skip it.
+pub const MARKER_FILE: &str = ".gitfetch-archive";
+
+/// Origin of an extracted snapshot, standing in for `.git` in a checkout
+pub struct ArchiveMarker {
+    pub url: String,
+    pub commit: String,
+}
+
+/// The marker of a snapshot directory. Git checkouts never have one, a committed
+/// marker file must not be able to spoof the identity of a real clone.
+pub fn read_marker(dir: &Path) -> Option<ArchiveMarker> {
+    if dir.join(".git").exists() {
+        return None;
+    }
+
+    let contents = fs::read_to_string(dir.join(MARKER_FILE)).ok()?;
+    let mut url = None;
+    let mut commit = None;
+    for line in contents.lines() {
+        if let Some(value) = line.strip_prefix("url=") {
+            url = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("commit=") {
+            commit = Some(value.to_string());
+        }
+    }
+
+    Some(ArchiveMarker { url: url?, commit: commit? })
+}
+
+pub fn write_marker(dir: &Path, url: &str, commit: &str) -> Result<(), String> {
+    let path = dir.join(MARKER_FILE);
+    // Whatever the archive put there goes, including a symlink pointing elsewhere
+    let _ = fs::remove_file(&path);
+    fs::write(&path, format!("url={}\ncommit={}\n", url, commit))
+        .map_err(|e| format!("Can't write {}: {}", path.display(), e))
+}
+
+/// Extract a `.tar.gz` snapshot into `dest`, dropping the top-level directory
+/// GitHub wraps it in. Refuses entries that would land outside `dest`, symlinks
+/// that resolve outside it, `.git` directories and anything but plain files,
+/// directories and symlinks. Returns the number of files written.
+pub fn extract_tarball(tarball: &Path, dest: &Path) -> Result<usize, String> {
+    let file = fs::File::open(tarball).map_err(|e| format!("Can't open {}: {}", tarball.display(), e))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+    fs::create_dir_all(dest).map_err(|e| format!("Can't create {}: {}", dest.display(), e))?;
+    let root = dest.canonicalize().map_err(|e| format!("Can't resolve {}: {}", dest.display(), e))?;
+
+    let mut files = 0;
+    // Created last, so nothing is ever written through a symlink from the archive
+    let mut symlinks: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+    let entries = archive.entries().map_err(|e| format!("Invalid archive: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Invalid archive: {}", e))?;
+        let kind = entry.header().entry_type();
+
+        // pax headers (GitHub stores the commit id in one) carry no files
+        if kind.is_pax_global_extensions() || kind.is_pax_local_extensions() {
+            continue;
+        }
+
+        let raw_path = entry.path().map_err(|e| format!("Invalid path in archive: {}", e))?.into_owned();
+        let Some(relative) = safe_relative(&raw_path)? else { continue };
+        let target = root.join(&relative);
+
+        if kind.is_dir() {
+            fs::create_dir_all(&target).map_err(|e| format!("Can't create {}: {}", relative.display(), e))?;
+        } else if kind.is_file() {
+            let parent = target.parent().unwrap();
+            fs::create_dir_all(parent).map_err(|e| format!("Can't create {}: {}", parent.display(), e))?;
+            ensure_within(&root, parent)?;
+
+            let mut out = fs::File::create(&target).map_err(|e| format!("Can't write {}: {}", relative.display(), e))?;
+            io::copy(&mut entry, &mut out).map_err(|e| format!("Can't write {}: {}", relative.display(), e))?;
+
+            // Keep the executable bit, nothing else (no setuid, no world-writable files)
+            let mode = entry.header().mode().unwrap_or(0o644);
+            let mode = if mode & 0o111 != 0 { 0o755 } else { 0o644 };
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode))
+                .map_err(|e| format!("Can't set mode of {}: {}", relative.display(), e))?;
+            files += 1;
+        } else if kind.is_symlink() {
+            let link = entry.link_name()
+                .map_err(|e| format!("Invalid symlink {}: {}", relative.display(), e))?
+                .ok_or_else(|| format!("Symlink {} has no target", relative.display()))?
+                .into_owned();
+            if link.is_absolute() || !stays_within(&relative, &link) {
+                return Err(format!("Refusing symlink {} -> {} pointing outside the repository", relative.display(), link.display()));
+            }
+            symlinks.push((relative, link));
+        } else {
+            return Err(format!("Refusing {} ({:?} entries aren't allowed)", relative.display(), kind));
+        }
+    }
+
+    for (relative, link) in &symlinks {
+        let path = root.join(relative);
+        let parent = path.parent().unwrap();
+        fs::create_dir_all(parent).map_err(|e| format!("Can't create {}: {}", parent.display(), e))?;
+        ensure_within(&root, parent)?;
+        if path.symlink_metadata().is_ok() {
+            return Err(format!("Refusing symlink {}, the archive also has files at that path", relative.display()));
+        }
+        std::os::unix::fs::symlink(link, &path)
+            .map_err(|e| format!("Can't create symlink {}: {}", relative.display(), e))?;
+    }
+
+    // Chains of in-tree symlinks can still climb out (`b -> a/..` before `a -> .`),
+    // so only check once every link exists. A link that doesn't resolve is refused too,
+    // it could be completed into an escape later.
+    for (relative, link) in &symlinks {
+        let resolved = root.join(relative).canonicalize();
+        if !resolved.is_ok_and(|resolved| resolved.starts_with(&root)) {
+            return Err(format!("Refusing symlink {} -> {} resolving outside the repository", relative.display(), link.display()));
+        }
+    }
+
+    Ok(files)
+}
+
+/// The entry path without its top-level directory, `None` for the top-level itself.
+/// Rejects absolute paths, `..` and `.git` components.
+fn safe_relative(path: &Path) -> Result<Option<PathBuf>, String> {
+    let mut relative = PathBuf::new();
+    for component in path.components().skip(1) {
+        match component {
+            Component::Normal(part) if part == ".git" || part == MARKER_FILE => {
+                return Err(format!("Refusing archive entry {}", path.display()));
+            }
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return Err(format!("Refusing archive entry {} escaping the repository", path.display())),
+        }
+    }
+
+    if let Some(Component::RootDir | Component::Prefix(_) | Component::ParentDir) = path.components().next() {
+        return Err(format!("Refusing archive entry {} escaping the repository", path.display()));
+    }
+
+    Ok(Some(relative).filter(|relative| !relative.as_os_str().is_empty()))
+}
+
+/// Whether `link`, read relative to the directory of `entry`, stays inside the root
+fn stays_within(entry: &Path, link: &Path) -> bool {
+    let mut depth = entry.components().count() as i64 - 1;
+    for component in link.components() {
+        match component {
+            Component::ParentDir => depth -= 1,
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            _ => return false,
+        }
+        if depth < 0 {
+            return false;
+        }
+    }
+    true
+}
+
+fn ensure_within(root: &Path, dir: &Path) -> Result<(), String> {
+    let resolved = dir.canonicalize().map_err(|e| format!("Can't resolve {}: {}", dir.display(), e))?;
+    if resolved.starts_with(root) {
+        Ok(())
+    } else {
+        Err(format!("Refusing to write outside the repository: {}", dir.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_tarball, safe_relative, stays_within};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Extract a tarball of `repo/<name> -> <target>` symlinks and a README into a scratch dir
+    fn extract_links(name: &str, links: &[(&str, &str)]) -> (PathBuf, Result<usize, String>) {
+        let scratch = std::env::temp_dir().join(format!("gitfetch-archive-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&scratch);
+        fs::create_dir_all(&scratch).unwrap();
+
+        let tarball = scratch.join("snapshot.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(fs::File::create(&tarball).unwrap(), Compression::default()));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(6);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, "repo/README", &b"hello\n"[..]).unwrap();
+        for (path, target) in links {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, format!("repo/{}", path), target).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let dest = scratch.join("dest");
+        let result = extract_tarball(&tarball, &dest);
+        (scratch, result)
+    }
+
+    #[test]
+    fn top_level_directory_is_stripped() {
+        assert_eq!(safe_relative(Path::new("repo-1.0/src/main.rs")), Ok(Some(PathBuf::from("src/main.rs"))));
+        assert_eq!(safe_relative(Path::new("repo-1.0/./src/main.rs")), Ok(Some(PathBuf::from("src/main.rs"))));
+        assert_eq!(safe_relative(Path::new("repo-1.0/")), Ok(None));
+    }
+
+    #[test]
+    fn parent_dirs_are_refused() {
+        assert!(safe_relative(Path::new("repo-1.0/../evil")).is_err());
+        assert!(safe_relative(Path::new("repo-1.0/src/../../evil")).is_err());
+        assert!(safe_relative(Path::new("../repo-1.0/evil")).is_err());
+    }
+
+    #[test]
+    fn absolute_paths_are_refused() {
+        assert!(safe_relative(Path::new("/etc/passwd")).is_err());
+        assert!(safe_relative(Path::new("/repo-1.0/src/main.rs")).is_err());
+    }
+
+    #[test]
+    fn git_metadata_is_refused() {
+        assert!(safe_relative(Path::new("repo-1.0/.git/config")).is_err());
+        assert!(safe_relative(Path::new("repo-1.0/sub/.git/hooks/post-checkout")).is_err());
+        assert!(safe_relative(Path::new("repo-1.0/.gitfetch-archive")).is_err());
+    }
+
+    #[test]
+    fn symlinks_inside_the_root() {
+        assert!(stays_within(Path::new("link"), Path::new("target")));
+        assert!(stays_within(Path::new("docs/link"), Path::new("../README.md")));
+        assert!(stays_within(Path::new("a/b/link"), Path::new("../../c/./d")));
+        assert!(stays_within(Path::new("a/link"), Path::new("b/../../c")));
+    }
+
+    #[test]
+    fn symlinks_escaping_the_root() {
+        assert!(!stays_within(Path::new("link"), Path::new("../outside")));
+        assert!(!stays_within(Path::new("docs/link"), Path::new("../../outside")));
+        // Escaping halfway counts even if the path comes back in
+        assert!(!stays_within(Path::new("link"), Path::new("../repo/file")));
+        assert!(!stays_within(Path::new("a/link"), Path::new("/etc/passwd")));
+    }
+
+    #[test]
+    fn symlinks_within_the_tree_are_extracted() {
+        let (scratch, result) = extract_links("ok", &[("docs/readme", "../README"), ("here", ".")]);
+        assert_eq!(result, Ok(1));
+        assert_eq!(fs::read_to_string(scratch.join("dest/docs/readme")).unwrap(), "hello\n");
+        fs::remove_dir_all(scratch).unwrap();
+    }
+
+    #[test]
+    fn symlink_chains_escaping_the_root_are_refused() {
+        // `b` dangles when it is created and only escapes once `a` exists
+        let (scratch, result) = extract_links("chain", &[("b", "a/.."), ("a", ".")]);
+        assert!(result.is_err());
+        fs::remove_dir_all(scratch).unwrap();
+    }
+
+    #[test]
+    fn dangling_symlinks_are_refused() {
+        let (scratch, result) = extract_links("dangling", &[("link", "missing")]);
+        assert!(result.is_err());
+        fs::remove_dir_all(scratch).unwrap();
+    }
+}
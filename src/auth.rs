@@ -0,0 +1,145 @@
+use crate::config::GitFetchConfig;
+use crate::forge::DEFAULT_FORGE;
+use crate::types::StoredToken;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::process::{Command, Stdio};
+
+/// Environment variables with a token for the default forge, checked in this order
+pub const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// Username tokens are filed under with the git credential helper
+const CREDENTIAL_USER: &str = "gitfetch";
+
+/// Where a forge's token comes from
+pub enum TokenSource {
+    Env(&'static str),
+    File,
+    Git,
+}
+
+impl std::fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TokenSource::Env(var) => write!(f, "${}", var),
+            TokenSource::File => write!(f, "{}", GitFetchConfig::tokens_path().display()),
+            TokenSource::Git => write!(f, "git credential helper"),
+        }
+    }
+}
+
+/// Where the token for `forge` would come from, without reading it from the credential helper
+pub fn source(forge: &str) -> Option<TokenSource> {
+    if forge == DEFAULT_FORGE {
+        if let Some(var) = TOKEN_VARS.into_iter().find(|var| std::env::var(var).is_ok_and(|value| !value.is_empty())) {
+            return Some(TokenSource::Env(var));
+        }
+    }
+
+    match load_stored().get(forge)? {
+        StoredToken::File { .. } => Some(TokenSource::File),
+        StoredToken::Git => Some(TokenSource::Git),
+    }
+}
+
+/// The token for the API of `forge` at `api`: from the environment (default forge only),
+/// the tokens file or the git credential helper
+pub fn token(forge: &str, api: &str) -> Option<String> {
+    match source(forge)? {
+        TokenSource::Env(var) => std::env::var(var).ok(),
+        TokenSource::File => match load_stored().remove(forge)? {
+            StoredToken::File { token } => Some(token),
+            StoredToken::Git => None,
+        },
+        TokenSource::Git => match credential("fill", api, None) {
+            Ok(token) => token,
+            Err(e) => {
+                eprintln!("⚠️  No token for {} from the git credential helper: {}", forge, e);
+                None
+            }
+        },
+    }
+}
+
+pub fn load_stored() -> HashMap<String, StoredToken> {
+    let path = GitFetchConfig::tokens_path();
+    let Ok(metadata) = fs::metadata(&path) else {
+        return HashMap::new();
+    };
+
+    // Like ssh with private keys, ignore tokens other users can read
+    if metadata.permissions().mode() & 0o077 != 0 {
+        eprintln!("⚠️  Ignoring {}, it is readable by others (chmod 600 it)", path.display());
+        return HashMap::new();
+    }
+
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_stored(tokens: &HashMap<String, StoredToken>) -> Result<(), String> {
+    let path = GitFetchConfig::tokens_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Can't create {}: {}", parent.display(), e))?;
+    }
+
+    // Write next to the file and rename over it, so a crash never leaves it truncated
+    let temp = path.with_extension("json.tmp");
+    let _ = fs::remove_file(&temp);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&temp)
+        .map_err(|e| format!("Can't write {}: {}", temp.display(), e))?;
+
+    let contents = serde_json::to_string_pretty(tokens).expect("Can't serialize tokens");
+    let written = file.write_all(contents.as_bytes()).and_then(|_| file.sync_all());
+    if let Err(e) = written.and_then(|_| fs::rename(&temp, &path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Can't write {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+/// Run `git credential <action>` for the token of the API at `api`. Only `fill` returns a token.
+pub fn credential(action: &str, api: &str, token: Option<&str>) -> Result<Option<String>, String> {
+    let (protocol, rest) = api.split_once("://").ok_or_else(|| format!("Invalid API URL {}", api))?;
+    let host = rest.split('/').next().unwrap_or(rest);
+
+    let mut input = format!("protocol={}\nhost={}\nusername={}\n", protocol, host, CREDENTIAL_USER);
+    if let Some(token) = token {
+        input.push_str(&format!("password={}\n", token));
+    }
+    input.push('\n');
+
+    // Never fall back to asking on the terminal or through an askpass program
+    let mut child = Command::new("git")
+        .args(["-c", "core.askPass=", "credential", action])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env_remove("GIT_ASKPASS")
+        .env_remove("SSH_ASKPASS")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run git credential: {}", e))?;
+
+    child.stdin.take().expect("stdin is piped").write_all(input.as_bytes())
+        .map_err(|e| format!("Failed to talk to git credential: {}", e))?;
+    let output = child.wait_with_output()
+        .map_err(|e| format!("Failed to run git credential: {}", e))?;
+
+    if !output.status.success() {
+        return Err("nothing stored".to_string());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .map(str::to_string))
+}
@@ -1,4 +1,5 @@
 use crate::git;
+use crate::lfs;
 use crate::types::RepoChecksum;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -49,12 +50,15 @@ pub fn calculate_repo_checksums(repo_path: &str) -> io::Result<RepoChecksum> {
     
     let verified_at = chrono::Utc::now().to_rfc3339();
     
+    let lfs_materialized = lfs::uses_lfs(path).then(|| lfs::find_pointers(path).is_empty());
+    
     Ok(RepoChecksum {
         repo_url,
         commit_hash,
         file_checksums,
         total_hash,
         verified_at,
+        lfs_materialized,
     })
 }
 
@@ -140,6 +144,15 @@ pub fn verify_repo_checksums(repo_path: &str, expected: &RepoChecksum) -> Result
     }
     
     println!("✓ Verified: {} | ✗ Issues: {}", verified, issues);
+
+    if let Some(expected_lfs) = expected.lfs_materialized {
+        let current_lfs = lfs::find_pointers(path).is_empty();
+        if expected_lfs && !current_lfs {
+            println!("⚠️  Checksum was taken with LFS content, this checkout still has LFS pointers");
+        } else if !expected_lfs && current_lfs {
+            println!("⚠️  Checksum was taken from LFS pointers, this checkout has the LFS content");
+        }
+    }
     
     if all_match {
         println!("✓ Repository integrity verified!");
@@ -0,0 +1,154 @@
+use crate::checksum;
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::security;
+use crate::types::InstalledRepo;
+use super::clone::{copy_dir, repo_name_from_url, security_scan};
+use std::fs;
+use std::path::Path;
+
+pub fn adopt_repo(path: &str, trust_mode: &str) {
+    security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
+
+    let repo_path = Path::new(path).canonicalize().unwrap_or_else(|e| {
+        eprintln!("Can't resolve {}: {}", path, e);
+        std::process::exit(1);
+    });
+    let path_str = repo_path.to_string_lossy().to_string();
+
+    // Worktrees and submodules have a .git file pointing elsewhere
+    if !repo_path.join(".git").is_dir() {
+        eprintln!("{} is not a standalone git clone", path_str);
+        std::process::exit(1);
+    }
+
+    // First, the host git reads this repo next and its config could make git run programs
+    println!("\n{}", "=".repeat(60));
+    println!("SANITIZING GIT CONFIG");
+    println!("{}", "=".repeat(60));
+
+    match security::sanitize_git_dir(&repo_path) {
+        Ok(changes) if changes.is_empty() => println!("No hooks or risky config found."),
+        Ok(changes) => {
+            for change in changes {
+                println!("  {}", change);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let repo_url = git::get_remote_url(&path_str).unwrap_or_else(|| {
+        eprintln!("{} has no origin remote", path_str);
+        std::process::exit(1);
+    });
+    let commit_hash = git::get_commit_hash(&path_str).unwrap_or_else(|| {
+        eprintln!("{} has no commits", path_str);
+        std::process::exit(1);
+    });
+
+    let repo_id = git::repo_id(&repo_url);
+    let repo_name = repo_name_from_url(&repo_url);
+
+    let config = GitFetchConfig::load();
+    if let Some(existing) = config.find_repo(&repo_id) {
+        eprintln!("{} is already managed by gitfetch at {}", repo_id, existing.path);
+        std::process::exit(1);
+    }
+    if let Some(existing) = config.installed_repos.iter().find(|repo| Path::new(&repo.path) == repo_path) {
+        eprintln!("{} is already installed there as {}", path_str, existing.id);
+        std::process::exit(1);
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("ADOPTING: {}", path_str);
+    println!("{}", "=".repeat(60));
+    println!("Remote:     {}", repo_url);
+    println!("Commit:     {}", commit_hash);
+    println!("Trust mode: {}", trust_mode);
+
+    // Adopted clones are recorded without a submodule policy, their submodules weren't fetched by gitfetch
+    let warnings = security_scan(&repo_path, false);
+
+    if !warnings.is_empty()
+        && trust_mode == "paranoid"
+        && !security::prompt_user("\nSuspicious code detected. Adopt anyway? (yes/no)")
+    {
+        println!("Adopt cancelled.");
+        return;
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("CHECKSUMS");
+    println!("{}", "=".repeat(60));
+
+    let actual = checksum::calculate_repo_checksums(&path_str).unwrap_or_else(|e| {
+        eprintln!("Failed to calculate checksums: {}", e);
+        std::process::exit(1);
+    });
+
+    // Never replace a registry entry that came from somewhere else, and never trust the
+    // unverified local tree on its own: that's for the user to decide with `checksum --save`
+    let mut config = GitFetchConfig::load();
+    let mut verified = false;
+    let has_checksum = config.get_checksum(&repo_url).is_some();
+    match config.get_checksum(&repo_url) {
+        Some(expected) if expected.commit_hash == commit_hash => {
+            verified = expected.total_hash == actual.total_hash;
+            if verified {
+                println!("✓ Matches the registry checksum");
+            } else {
+                println!("⚠️  Differs from the registry checksum for this commit (local changes?)");
+            }
+        }
+        Some(expected) => {
+            println!("Registry has a checksum for {:.8}, leaving it alone", expected.commit_hash);
+        }
+        None => {
+            println!("No registry checksum, total hash is {}", actual.total_hash);
+        }
+    }
+
+    // update and rollback work from a checkout in the workspace, like a clone
+    let workspace = GitFetchConfig::workspace_root().join(&repo_id);
+    if workspace.exists() {
+        fs::remove_dir_all(&workspace).expect("Can't remove stale workspace");
+    }
+    fs::create_dir_all(&workspace).expect("Can't create workspace");
+
+    if !copy_dir(&repo_path, &workspace.join(&repo_name)) {
+        let _ = fs::remove_dir_all(&workspace);
+        eprintln!("Failed to copy {} into the workspace", path_str);
+        std::process::exit(1);
+    }
+
+    config.add_repo(InstalledRepo {
+        id: repo_id.clone(),
+        name: repo_name,
+        url: repo_url,
+        path: path_str.clone(),
+        commit_hash: Some(commit_hash),
+        verified,
+        workspace_path: Some(workspace.to_string_lossy().to_string()),
+        git_ref: None,
+        history: Vec::new(),
+        depth: None,
+        filter: None,
+        sparse_paths: Vec::new(),
+        submodules: None,
+        lfs_max_size: None,
+        archive: false,
+        scan_warnings: Some(warnings),
+    });
+
+    println!("\n{}", "=".repeat(60));
+    println!("✓ ADOPTED {}", repo_id);
+    println!("{}", "=".repeat(60));
+    println!("Location: {}", path_str);
+    if !has_checksum {
+        println!("\n💡 Create checksum: gitfetch checksum {} --save", path_str);
+    }
+    println!("💡 Updates replace this directory with the upstream version, commit or stash local work first");
+}
@@ -0,0 +1,110 @@
+use crate::auth::{self, TokenSource};
+use crate::config::GitFetchConfig;
+use crate::forge;
+use crate::types::{ForgeKind, StoredToken};
+use std::io::{self, IsTerminal, Write};
+use std::process::Command;
+
+pub fn auth_login(forge_name: &str, git_credential: bool) {
+    let config = GitFetchConfig::load();
+    let api = forge::api_url(&config, forge_name).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    print!("Token for {} ({}): ", forge_name, api);
+    io::stdout().flush().expect("Failed to flush stdout");
+    let token = read_secret();
+    println!();
+    if token.is_empty() {
+        eprintln!("No token given, nothing saved.");
+        std::process::exit(1);
+    }
+
+    let mut stored = auth::load_stored();
+    let entry = if git_credential {
+        if let Err(e) = auth::credential("approve", &api, Some(&token)) {
+            eprintln!("Can't hand the token to the git credential helper: {}", e);
+            std::process::exit(1);
+        }
+        StoredToken::Git
+    } else {
+        StoredToken::File { token }
+    };
+    stored.insert(forge_name.to_string(), entry);
+
+    if let Err(e) = auth::save_stored(&stored) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    println!("✓ Token for {} saved ({})", forge_name, auth::source(forge_name).map(|source| source.to_string()).unwrap_or_default());
+    if let Some(TokenSource::Env(var)) = auth::source(forge_name) {
+        println!("💡 ${} is set and takes precedence over the saved token", var);
+    }
+}
+
+pub fn auth_logout(forge_name: &str) {
+    let config = GitFetchConfig::load();
+    let api = forge::api_url(&config, forge_name).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let mut stored = auth::load_stored();
+    match stored.remove(forge_name) {
+        Some(StoredToken::Git) => {
+            // Nothing to forget isn't an error here
+            let _ = auth::credential("reject", &api, None);
+        }
+        Some(StoredToken::File { .. }) => {}
+        None => {
+            println!("No saved token for {}.", forge_name);
+            return;
+        }
+    }
+
+    if let Err(e) = auth::save_stored(&stored) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    println!("✓ Removed token for {}", forge_name);
+}
+
+pub fn auth_status() {
+    let config = GitFetchConfig::load();
+
+    println!("\n{}", "=".repeat(60));
+    println!("API TOKENS");
+    println!("{}", "=".repeat(60));
+
+    for (name, profile) in forge::profiles(&config) {
+        if profile.kind == ForgeKind::Sourcehut {
+            continue;
+        }
+        let api = forge::api_url(&config, &name).unwrap_or_default();
+        match auth::source(&name) {
+            Some(source) => println!("  {:<12} {}  token from {}", name, api, source),
+            None => println!("  {:<12} {}  anonymous", name, api),
+        }
+    }
+
+    println!("\n💡 Tokens are only sent to the API URL of their forge, never into the sandbox.");
+}
+
+/// Read a line from stdin, without echoing it when stdin is a terminal
+fn read_secret() -> String {
+    let terminal = io::stdin().is_terminal();
+    if terminal {
+        let _ = Command::new("stty").arg("-echo").status();
+    }
+
+    let mut secret = String::new();
+    let read = io::stdin().read_line(&mut secret);
+
+    if terminal {
+        let _ = Command::new("stty").arg("echo").status();
+    }
+    read.expect("Failed to read input");
+    secret.trim().to_string()
+}
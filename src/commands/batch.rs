@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Result of one job in a batch, shown as a row of the summary table
+pub(crate) enum Outcome {
+    Done(String),
+    Skipped(String),
+    Failed(String),
+}
+
+/// Run `job` over every item with at most `jobs` running at once.
+/// Results come back in the order of `items`.
+pub(crate) fn run_parallel<T, R, F>(items: &[T], jobs: usize, job: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else { break };
+                let result = job(item);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    results.into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|result| result.expect("Batch job didn't finish"))
+        .collect()
+}
+
+/// Print one line per job and return how many failed
+pub(crate) fn print_summary(title: &str, results: &[(String, Outcome)]) -> usize {
+    let failed = results.iter().filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_))).count();
+
+    println!("\n{}", "=".repeat(60));
+    println!("{}", title);
+    println!("{}", "=".repeat(60));
+
+    for (name, outcome) in results {
+        match outcome {
+            Outcome::Done(detail) => println!("  ✓ {}  {}", name, detail),
+            Outcome::Skipped(detail) => println!("  - {}  {}", name, detail),
+            Outcome::Failed(error) => println!("  ✗ {}  {}", name, error),
+        }
+    }
+
+    if failed > 0 {
+        println!("\n{} of {} failed", failed, results.len());
+    }
+    failed
+}
@@ -1,6 +1,7 @@
 use crate::checksum;
 use crate::config::GitFetchConfig;
 use crate::git;
+use super::batch::{self, Outcome};
 
 pub fn checksum_command(path: &str, save: bool) {
     let abs_path = std::path::Path::new(path)
@@ -30,32 +31,71 @@ pub fn checksum_command(path: &str, save: bool) {
     }
 }
 
-pub fn verify_command(path: &str) {
+pub fn verify_command(targets: &[String], all: bool, jobs: usize) {
+    // Accept installed repos' names as well as paths
+    let paths: Vec<String> = if all {
+        GitFetchConfig::load().installed_repos.iter().map(|repo| repo.path.clone()).collect()
+    } else {
+        targets.iter().map(|target| resolve_verify_path(target)).collect()
+    };
+
+    if paths.is_empty() {
+        println!("No repositories installed yet.");
+        return;
+    }
+
+    if let [path] = paths.as_slice() {
+        match verify_path(path) {
+            Ok(true) => std::process::exit(0),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let results = batch::run_parallel(&paths, jobs, |path| {
+        let outcome = match verify_path(path) {
+            Ok(true) => Outcome::Done("verified".to_string()),
+            Ok(false) => Outcome::Failed("checksum mismatch".to_string()),
+            Err(e) => Outcome::Failed(e),
+        };
+        (path.clone(), outcome)
+    });
+
+    if batch::print_summary("VERIFY SUMMARY", &results) > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn resolve_verify_path(target: &str) -> String {
+    if std::path::Path::new(target).exists() {
+        return target.to_string();
+    }
+
+    match GitFetchConfig::load().resolve_repo(target) {
+        Ok(repo) => repo.path.clone(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn verify_path(path: &str) -> Result<bool, String> {
     let abs_path = std::path::Path::new(path)
         .canonicalize()
-        .expect("Can't resolve path")
+        .map_err(|e| format!("Can't resolve {}: {}", path, e))?
         .to_string_lossy()
         .to_string();
     
     let repo_url = git::get_remote_url(&abs_path)
-        .expect("Can't get repository URL");
+        .ok_or_else(|| format!("Can't get repository URL of {}", abs_path))?;
     
     let config = GitFetchConfig::load();
-    
-    match config.get_checksum(&repo_url) {
-        Some(expected) => {
-            match checksum::verify_repo_checksums(&abs_path, expected) {
-                Ok(true) => std::process::exit(0),
-                Ok(false) => std::process::exit(1),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        }
-        None => {
-            eprintln!("No checksum found for: {}", repo_url);
-            std::process::exit(1);
-        }
-    }
+    let expected = config.get_checksum(&repo_url)
+        .ok_or_else(|| format!("No checksum found for: {}", repo_url))?;
+
+    checksum::verify_repo_checksums(&abs_path, expected)
 }
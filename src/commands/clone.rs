@@ -1,35 +1,159 @@
+use crate::archive;
 use crate::checksum;
 use crate::config::GitFetchConfig;
+use crate::forge;
 use crate::git;
+use crate::http;
+use crate::lfs;
+use crate::mirror;
 use crate::security;
+use crate::submodules;
+use crate::types::InstalledRepo;
+use super::batch::{self, Outcome};
+use super::outdated::{ls_remote, pick_ref};
+use super::release::download;
+use super::rollback::archive_installed;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
+/// Knobs for a single run of the staged fetch/checkout pipeline
+#[derive(Default)]
+pub(crate) struct CloneOptions<'a> {
+    pub verify_checksum: bool,
+    pub trust_mode: &'a str,
+    /// Ref recorded in the config (and checked out unless `commit` is set)
+    pub git_ref: Option<&'a str>,
+    /// Exact commit to check out, e.g. from a lockfile
+    pub commit: Option<&'a str>,
+    /// Fail unless the checkout hashes to this `RepoChecksum::total_hash`
+    pub expected_total_hash: Option<&'a str>,
+    /// Where to install the copy, defaults to `./<repo name>`
+    pub install_path: Option<PathBuf>,
+    /// Shallow clone with this many commits of history
+    pub depth: Option<u32>,
+    /// Partial clone filter, e.g. `blob:none`
+    pub filter: Option<&'a str>,
+    /// Only check out these directories (cone mode sparse checkout)
+    pub sparse_paths: &'a [String],
+    /// Submodule policy: `none` (or unset), `prompt` or `recursive`
+    pub submodules: Option<&'a str>,
+    /// Download LFS objects up to this many MB each, pointers are left alone when unset
+    pub lfs_max_size: Option<u64>,
+    /// Never enable the network, clone from the mirror cache or `bundle`
+    pub offline: bool,
+    /// Git bundle to clone from when offline
+    pub bundle: Option<&'a Path>,
+    /// Download a source tarball of the commit instead of cloning
+    pub archive: bool,
+}
+
+pub fn clone_repo(repos: &[String], jobs: usize, options: &CloneOptions) {
     security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
     
-    let repo_url = if repo.starts_with("http://") || repo.starts_with("https://") {
-        repo.to_string()
-    } else if repo.contains('/') {
-        format!("https://github.com/{}", repo)
-    } else {
-        eprintln!("Invalid repository format");
+    let mut repo_urls: Vec<String> = Vec::new();
+    for repo in repos {
+        let repo_url = expand_repo_url(repo).unwrap_or_else(|e| {
+            eprintln!("{}: {}", repo, e);
+            std::process::exit(1);
+        });
+        if !repo_urls.iter().any(|url| git::repo_id(url) == git::repo_id(&repo_url)) {
+            repo_urls.push(repo_url);
+        }
+    }
+    
+    if options.bundle.is_some() && repo_urls.len() > 1 {
+        eprintln!("A bundle holds one repository, clone the others separately");
         std::process::exit(1);
-    };
+    }
+    
+    if let [repo_url] = repo_urls.as_slice() {
+        match install_repo(repo_url, options) {
+            Ok(Some(_)) => {}
+            Ok(None) => std::process::exit(0),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    
+    // Every clone lands in ./<name>, two repos with the same name would fight over it
+    for (index, repo_url) in repo_urls.iter().enumerate() {
+        let name = repo_name_from_url(repo_url);
+        if let Some(other) = repo_urls[..index].iter().find(|url| repo_name_from_url(url) == name) {
+            eprintln!("{} and {} would both install into ./{}, clone them separately", other, repo_url, name);
+            std::process::exit(1);
+        }
+    }
+    
+    let results = batch::run_parallel(&repo_urls, jobs, |repo_url| {
+        let outcome = match install_repo(repo_url, options) {
+            Ok(Some(installed)) => Outcome::Done(format!(
+                "{:.8} at {}",
+                installed.commit_hash.as_deref().unwrap_or("unknown"),
+                installed.path
+            )),
+            Ok(None) => Outcome::Skipped("cancelled".to_string()),
+            Err(e) => Outcome::Failed(e),
+        };
+        (git::repo_id(repo_url), outcome)
+    });
+    
+    if batch::print_summary("CLONE SUMMARY", &results) > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Check a clone URL, or turn `owner/repo` or `<forge>:path` shorthand into one
+pub(crate) fn expand_repo_url(repo: &str) -> Result<String, String> {
+    if repo.contains("://") || repo.contains("::") || git::is_scp_style(repo) {
+        let remote = git::parse_remote(repo)?;
+        if remote.path.is_empty() {
+            return Err(format!("No repository path in {}", repo));
+        }
+        Ok(repo.to_string())
+    } else {
+        forge::expand_shorthand(&GitFetchConfig::load(), repo)
+    }
+}
 
-    let repo_name = repo_url
+/// Derive the local directory name from a clone URL
+pub(crate) fn repo_name_from_url(repo_url: &str) -> String {
+    let path = git::parse_remote(repo_url)
+        .map(|remote| remote.path)
+        .unwrap_or_else(|_| repo_url.to_string());
+    path
+        .trim_end_matches('/')
         .trim_end_matches(".git")
-        .split('/')
-        .last()
+        .rsplit(['/', ':'])
+        .next()
         .expect("Can't parse repo name")
-        .to_string();
+        .to_string()
+}
 
-    let home = std::env::var("HOME").expect("No HOME?");
-    let workspace_base = PathBuf::from(home).join(".gitfetch").join("workspace");
+/// Run the full staged pipeline: sandboxed fetch, offline checkout, verification,
+/// scan, copy and config record. Returns `Ok(None)` when the user cancels.
+pub(crate) fn install_repo(repo_url: &str, options: &CloneOptions) -> Result<Option<InstalledRepo>, String> {
+    let trust_mode = options.trust_mode;
+    let repo_name = repo_name_from_url(repo_url);
+    let repo_id = git::repo_id(repo_url);
+    
+    if !GitFetchConfig::load().is_host_allowed(repo_url) {
+        return Err(format!("{} is not in allowed_hosts", git::repo_host(repo_url)));
+    }
+
+    let workspace_base = GitFetchConfig::workspace_root();
     fs::create_dir_all(&workspace_base).expect("Can't create workspace");
     
-    let workspace = workspace_base.join(&repo_name);
+    // Namespaced by host/owner/name so same-named repos don't clobber each other
+    let workspace = workspace_base.join(&repo_id);
+    
+    // Keep the last known-good copy before the workspace gets wiped
+    if let Some(previous) = GitFetchConfig::load().find_repo(&repo_id) {
+        archive_installed(previous, None);
+    }
     
     if workspace.exists() {
         fs::remove_dir_all(&workspace).expect("Can't remove existing workspace");
@@ -41,65 +165,82 @@ pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
     println!("CLONING: {}", repo_url);
     println!("{}", "=".repeat(60));
     println!("Trust mode: {}", trust_mode);
+    if let Some(git_ref) = options.git_ref {
+        println!("Ref:        {}", git_ref);
+    }
+    if let Some(depth) = options.depth {
+        println!("Depth:      {}", depth);
+    }
+    if let Some(filter) = options.filter {
+        println!("Filter:     {}", filter);
+    }
+    if !options.sparse_paths.is_empty() {
+        println!("Sparse:     {}", options.sparse_paths.join(", "));
+    }
+    if options.archive {
+        println!("Mode:       archive (no git history)");
+    }
     
     let config = GitFetchConfig::load();
-    let has_checksum = config.get_checksum(&repo_url).is_some();
+    let has_checksum = config.get_checksum(repo_url).is_some();
     
-    if verify_checksum && !has_checksum {
-        eprintln!("No checksum registry found (--verify-checksum specified)");
-        std::process::exit(1);
+    if options.verify_checksum && !has_checksum {
+        let _ = fs::remove_dir_all(&workspace);
+        return Err("No checksum registry found (--verify-checksum specified)".to_string());
     }
     
     // Paranoid mode: always prompt
-    // Normal mode: prompt unless checksum verified
+    // Normal mode: prompt unless checksum verified (or pinned by a lockfile)
     // YOLO mode: never prompt
     let should_prompt = match trust_mode {
         "paranoid" => true,
         "yolo" => false,
-        _ => !has_checksum, // normal
+        _ => !has_checksum && options.expected_total_hash.is_none(), // normal
     };
     
-    if should_prompt {
-        if !security::prompt_user("WARNING: Clone from untrusted source?\nProceed? (yes/no)") {
-            println!("Clone cancelled.");
-            std::process::exit(0);
-        }
-    }
-
-    println!("\n{}", "=".repeat(60));
-    println!("STAGE 1: FETCH (with network)");
-    println!("{}", "=".repeat(60));
-    
-    // Stage 1: Clone with --no-checkout (network enabled, no hooks run)
-    if let Err(e) = security::run_sandboxed_git(&workspace, &["clone", "--no-checkout", &repo_url], true) {
-        eprintln!("Clone failed: {}", e);
+    if should_prompt && !security::prompt_user(&format!("WARNING: Clone {} from untrusted source?\nProceed? (yes/no)", repo_id)) {
+        println!("Clone cancelled.");
         let _ = fs::remove_dir_all(&workspace);
-        std::process::exit(1);
+        return Ok(None);
     }
 
-    println!("\n{}", "=".repeat(60));
-    println!("STAGE 2: CHECKOUT (network isolated, hooks disabled)");
-    println!("{}", "=".repeat(60));
-    
     let repo_in_workspace = workspace.join(&repo_name);
     
-    // Stage 2: Checkout without network (hooks are disabled via env)
-    if let Err(e) = security::run_sandboxed_git(
-        &repo_in_workspace,
-        &["checkout", "--force", "HEAD"],
-        false // Network disabled
-    ) {
-        eprintln!("Checkout failed: {}", e);
+    let fetched = if options.archive {
+        fetch_archive(repo_url, options.commit.or(options.git_ref), &repo_in_workspace)
+    } else {
+        clone_and_checkout(repo_url, options, &workspace)
+    };
+    if let Err(e) = fetched {
         let _ = fs::remove_dir_all(&workspace);
-        std::process::exit(1);
+        return Err(e);
     }
 
-    let mut config = GitFetchConfig::load();
+    let config = GitFetchConfig::load();
     let commit_hash = git::get_commit_hash(repo_in_workspace.to_str().unwrap());
     
     // Verify checksums if available
     let mut verified = false;
-    if let Some(expected_checksum) = config.get_checksum(&repo_url) {
+    if let Some(expected_total_hash) = options.expected_total_hash {
+        println!("\n{}", "=".repeat(60));
+        println!("VERIFYING LOCKED HASH");
+        println!("{}", "=".repeat(60));
+        
+        let actual = checksum::calculate_repo_checksums(repo_in_workspace.to_str().unwrap())
+            .map_err(|e| format!("Failed to calculate checksums: {}", e))?;
+        
+        if actual.total_hash != expected_total_hash {
+            let _ = fs::remove_dir_all(&workspace);
+            return Err(format!(
+                "Total hash mismatch for {}\n  expected: {}\n  actual:   {}",
+                repo_url, expected_total_hash, actual.total_hash
+            ));
+        }
+        
+        println!("✓ Total hash matches: {}", actual.total_hash);
+        verified = true;
+        GitFetchConfig::edit(|config| config.add_checksum(repo_url.to_string(), actual));
+    } else if let Some(expected_checksum) = config.get_checksum(repo_url) {
         println!("\n{}", "=".repeat(60));
         println!("VERIFYING CHECKSUMS");
         println!("{}", "=".repeat(60));
@@ -112,7 +253,7 @@ pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
             Ok(false) => {
                 if trust_mode != "yolo" && !security::prompt_user("\nVerification failed. Proceed? (yes/no)") {
                     let _ = fs::remove_dir_all(&workspace);
-                    std::process::exit(0);
+                    return Ok(None);
                 }
             }
             Err(e) => eprintln!("Verification error: {}", e),
@@ -120,42 +261,46 @@ pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
     }
     
     // Security scan
-    println!("\n{}", "=".repeat(60));
-    println!("SECURITY SCAN");
-    println!("{}", "=".repeat(60));
-    
-    let warnings = security::scan_for_suspicious_patterns(repo_in_workspace.to_str().unwrap());
+    let warnings = security_scan(&repo_in_workspace, options.submodules.is_some_and(|policy| policy != "none"));
     
-    if !warnings.is_empty() {
-        println!("⚠️  {} suspicious patterns detected:", warnings.len());
-        for (i, warning) in warnings.iter().take(5).enumerate() {
-            println!("  {}. {}", i + 1, warning);
-        }
-        if warnings.len() > 5 {
-            println!("  ... and {} more", warnings.len() - 5);
-        }
-        
-        if trust_mode == "paranoid" && !security::prompt_user("\nSuspicious code detected. Proceed? (yes/no)") {
-            let _ = fs::remove_dir_all(&workspace);
-            std::process::exit(0);
-        }
-    } else {
-        println!("No obvious threats detected.");
+    if !warnings.is_empty()
+        && trust_mode == "paranoid"
+        && !security::prompt_user("\nSuspicious code detected. Proceed? (yes/no)")
+    {
+        let _ = fs::remove_dir_all(&workspace);
+        return Ok(None);
     }
     
-    // Copy to current directory
-    let current_dir = std::env::current_dir()
-        .expect("Can't get current directory")
-        .join(&repo_name);
+    // Copy to current directory (or the requested install path)
+    let install_path = options.install_path.clone().unwrap_or_else(|| {
+        std::env::current_dir()
+            .expect("Can't get current directory")
+            .join(&repo_name)
+    });
     
-    let final_path = if trust_mode != "paranoid" || security::prompt_user("\nCopy to current directory? (yes/no)") {
-        let copy_status = Command::new("cp")
-            .args(&["-r", repo_in_workspace.to_str().unwrap(), current_dir.to_str().unwrap()])
-            .status()
-            .expect("Failed to copy");
+    // Only overwrite an existing directory when it is our own previous install
+    let reinstall = install_path.exists() && config.installed_repos.iter().any(|repo| {
+        repo.id == repo_id && Path::new(&repo.path) == install_path
+    });
+    
+    let final_path = if trust_mode != "paranoid" || security::prompt_user(&format!("\nCopy to {}? (yes/no)", install_path.display())) {
+        if let Some(parent) = install_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        
+        let copied = if reinstall {
+            replace_installed_copy(&repo_in_workspace, &install_path)
+                .map_err(|e| eprintln!("{}", e))
+                .is_ok()
+        } else if install_path.exists() {
+            eprintln!("⚠️  {} already exists, leaving the checkout in the workspace", install_path.display());
+            false
+        } else {
+            copy_dir(&repo_in_workspace, &install_path)
+        };
         
-        if copy_status.success() {
-            current_dir.to_string_lossy().to_string()
+        if copied {
+            install_path.to_string_lossy().to_string()
         } else {
             repo_in_workspace.to_string_lossy().to_string()
         }
@@ -163,14 +308,25 @@ pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
         repo_in_workspace.to_string_lossy().to_string()
     };
     
-    config.add_repo(
-        repo_name.clone(),
-        repo_url.clone(),
-        final_path.clone(),
+    let installed = InstalledRepo {
+        id: repo_id,
+        name: repo_name,
+        url: repo_url.to_string(),
+        path: final_path.clone(),
         commit_hash,
         verified,
-        Some(workspace.to_string_lossy().to_string())
-    );
+        workspace_path: Some(workspace.to_string_lossy().to_string()),
+        git_ref: options.git_ref.map(String::from),
+        history: Vec::new(),
+        depth: options.depth,
+        filter: options.filter.map(String::from),
+        sparse_paths: options.sparse_paths.to_vec(),
+        submodules: options.submodules.filter(|policy| *policy != "none").map(String::from),
+        lfs_max_size: options.lfs_max_size,
+        archive: options.archive,
+        scan_warnings: Some(warnings),
+    };
+    GitFetchConfig::edit(|config| config.add_repo(installed.clone()));
 
     println!("\n{}", "=".repeat(60));
     println!("✓ CLONE COMPLETE");
@@ -180,4 +336,373 @@ pub fn clone_repo(repo: &str, verify_checksum: bool, trust_mode: &str) {
     if !verified && !has_checksum {
         println!("\n💡 Create checksum: gitfetch checksum {} --save", final_path);
     }
+    
+    Ok(Some(installed))
+}
+
+/// Git stages of the pipeline: fetch with network (or from the cache when offline),
+/// then check out with the network off. The caller cleans up the workspace on errors.
+fn clone_and_checkout(repo_url: &str, options: &CloneOptions, workspace: &Path) -> Result<(), String> {
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 1: FETCH ({})", if options.offline { "offline" } else { "with network" });
+    println!("{}", "=".repeat(60));
+    
+    // Offline the objects must already be on disk. Online, bring the local mirror
+    // up to date first, the clone then only borrows its objects.
+    let mirror = if options.offline {
+        let source = match options.bundle {
+            Some(bundle) => bundle.canonicalize()
+                .map_err(|e| format!("Can't read bundle {}: {}", bundle.display(), e))?,
+            None => mirror::existing(repo_url)
+                .ok_or_else(|| format!("No cached copy of {}, clone it once with network or pass --bundle", git::repo_id(repo_url)))?,
+        };
+        println!("Cloning from {}", source.display());
+        Some(source)
+    } else if mirror::applies(repo_url, options.depth, options.filter) {
+        println!("Updating object cache...");
+        mirror::refresh(repo_url)
+            .map_err(|e| println!("⚠️  Object cache unavailable ({}), cloning without it", e))
+            .ok()
+    } else {
+        None
+    };
+    
+    // Stage 1: Clone with --no-checkout (network enabled, no hooks run)
+    let mut clone_args = vec!["clone".to_string(), "--no-checkout".to_string()];
+    if mirror.is_some() && !options.offline {
+        // Dissociate so the workspace doesn't depend on the cache staying around
+        clone_args.extend(["--reference-if-able", security::MIRROR_MOUNT, "--dissociate"].map(String::from));
+    }
+    if let Some(depth) = options.depth {
+        clone_args.push(format!("--depth={}", depth));
+        // A shallow clone only has the default branch, fetch the pinned tag or branch instead
+        if let Some(wanted) = options.commit.or(options.git_ref).filter(|wanted| !is_full_sha(wanted)) {
+            clone_args.push(format!("--branch={}", wanted));
+        }
+    }
+    if let Some(filter) = options.filter {
+        clone_args.push(format!("--filter={}", filter));
+    }
+    if !options.sparse_paths.is_empty() {
+        clone_args.push("--sparse".to_string());
+    }
+    if options.offline {
+        clone_args.extend([security::MIRROR_MOUNT.to_string(), repo_name_from_url(repo_url)]);
+    } else {
+        clone_args.push(repo_url.to_string());
+    }
+    let clone_args: Vec<&str> = clone_args.iter().map(String::as_str).collect();
+
+    let cloned = match &mirror {
+        Some(mirror) => security::run_sandboxed_git_with_mirror(workspace, mirror, &clone_args, !options.offline),
+        None => security::run_sandboxed_fetch(workspace, repo_url, &clone_args),
+    };
+    cloned.map_err(|e| format!("Clone failed: {}", e))?;
+
+    let repo_in_workspace = workspace.join(repo_name_from_url(repo_url));
+    
+    // Point origin back at the real remote so later updates fetch from it
+    if options.offline {
+        security::run_sandboxed_git(&repo_in_workspace, &["remote", "set-url", "origin", repo_url], false)
+            .map_err(|e| format!("Can't set origin: {}", e))?;
+    }
+    
+    // Only writes .git/info/sparse-checkout, nothing is checked out yet
+    if !options.sparse_paths.is_empty() {
+        let mut sparse_args = vec!["sparse-checkout", "set", "--cone"];
+        sparse_args.extend(options.sparse_paths.iter().map(String::as_str));
+        security::run_sandboxed_git(&repo_in_workspace, &sparse_args, false)
+            .map_err(|e| format!("Sparse checkout setup failed: {}", e))?;
+    }
+    
+    let target = match options.commit.or(options.git_ref) {
+        Some(wanted) => {
+            let commit = resolve_pinned_ref(&repo_in_workspace, repo_url, wanted, options.depth, options.offline)?;
+            println!("Pinned {} -> {}", wanted, commit);
+            commit
+        }
+        None => "HEAD".to_string(),
+    };
+    
+    prefetch_blobs(&repo_in_workspace, &target, options.sparse_paths)?;
+    
+    // Listing submodules may need the network for missing trees, offline clones have none
+    let policy = options.submodules.unwrap_or("none");
+    let fetched_submodules = if options.offline {
+        Vec::new()
+    } else {
+        submodules::fetch_submodules(&repo_in_workspace, repo_url, &target, policy, options.trust_mode)?
+    };
+
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 2: CHECKOUT (network isolated, hooks disabled)");
+    println!("{}", "=".repeat(60));
+    
+    // Stage 2: Checkout without network (hooks are disabled via env)
+    security::run_sandboxed_git(
+        &repo_in_workspace,
+        &["-c", "advice.detachedHead=false", "checkout", "--force", &target],
+        false // Network disabled
+    )
+    .map_err(|e| format!("Checkout failed: {}", e))?;
+    
+    submodules::checkout_submodules(&repo_in_workspace, &fetched_submodules)?;
+    fetch_lfs_objects(&repo_in_workspace, repo_url, options.lfs_max_size)
+}
+
+/// Archive mode: resolve the commit, download its source tarball from the forge and extract it into
+/// `dest`. There is no `.git` afterwards, a marker file records the url and commit.
+fn fetch_archive(repo_url: &str, wanted: Option<&str>, dest: &Path) -> Result<(), String> {
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 1: FETCH (archive, with network)");
+    println!("{}", "=".repeat(60));
+
+    let config = GitFetchConfig::load();
+    let (_, forge, path) = forge::for_url(&config, repo_url)
+        .ok_or_else(|| format!("--archive needs a known forge, {} isn't one", git::repo_host(repo_url)))?;
+
+    // Tarballs are addressed by commit, so a moving branch can't change under us mid-download
+    let commit = match wanted {
+        Some(wanted) if is_full_sha(wanted) => wanted.to_lowercase(),
+        wanted => {
+            let refs = ls_remote(repo_url)?;
+            let (remote_ref, commit) = pick_ref(&refs, wanted)
+                .ok_or_else(|| format!("{} not found on remote", wanted.unwrap_or("HEAD")))?;
+            println!("Pinned {} -> {}", remote_ref, commit);
+            commit
+        }
+    };
+
+    let workspace = dest.parent().expect("Checkout path has no parent");
+    let tarball = workspace.join(format!("{}.tar.gz", commit));
+    let url = forge.archive_url(&path, &commit);
+    let client = http::client();
+
+    println!("Downloading {}...", url);
+    let sha256 = download(&client, &url, &tarball)?;
+    println!("Tarball SHA-256: {}", sha256);
+
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 2: EXTRACT (path traversal and symlink checks)");
+    println!("{}", "=".repeat(60));
+
+    let files = archive::extract_tarball(&tarball, dest)?;
+    let _ = fs::remove_file(&tarball);
+    archive::write_marker(dest, repo_url, &commit)?;
+    println!("✓ Extracted {} files at {:.8}", files, commit);
+
+    Ok(())
+}
+
+/// Resolve a requested tag, branch or commit to a commit that exists locally.
+/// Commits not advertised by any branch or tag are fetched explicitly (network stage),
+/// unless offline, where they have to be in the cache or bundle already.
+fn resolve_pinned_ref(repo_path: &Path, repo_url: &str, git_ref: &str, depth: Option<u32>, offline: bool) -> Result<String, String> {
+    let mut resolved = resolve_requested_ref(repo_path, git_ref);
+    
+    if resolved.is_none() && offline {
+        return Err(format!("{} is not available offline, it's missing from the cache or bundle", git_ref));
+    }
+    
+    if resolved.is_none() && is_full_sha(git_ref) {
+        let depth_arg = depth.map(|depth| format!("--depth={}", depth));
+        let mut fetch_args = vec!["fetch"];
+        fetch_args.extend(depth_arg.as_deref());
+        fetch_args.extend(["origin", git_ref]);
+        security::run_sandboxed_fetch(repo_path, repo_url, &fetch_args)
+            .map_err(|_| format!("Commit {} does not exist upstream", git_ref))?;
+        resolved = resolve_requested_ref(repo_path, git_ref);
+    }
+    
+    match resolved {
+        Some(commit) if is_full_sha(git_ref) && !commit.eq_ignore_ascii_case(git_ref) => {
+            Err(format!("Ref {} resolved to unexpected commit {}", git_ref, commit))
+        }
+        Some(commit) => Ok(commit),
+        None => Err(format!("Ref not found: {}", git_ref)),
+    }
+}
+
+/// Fetch every blob the checkout of `target` will read while the network is still up.
+/// Partial clones would otherwise fetch them lazily during the offline checkout and fail.
+pub(crate) fn prefetch_blobs(repo_path: &Path, target: &str, sparse_paths: &[String]) -> Result<(), String> {
+    let Ok(filter) = security::sandboxed_git_output(
+        repo_path,
+        &["config", "--get", "remote.origin.partialclonefilter"],
+        false,
+    ) else {
+        return Ok(()); // Not a partial clone, everything is local already
+    };
+
+    // Trees may be missing too (tree:0), so list them with the network up
+    let listing = security::sandboxed_git_output(repo_path, &["ls-tree", "-r", target], true)
+        .map_err(|e| format!("Can't list files of {}: {}", target, e))?;
+    let missing = security::sandboxed_git_output(
+        repo_path,
+        &["rev-list", "--objects", "--no-walk", "--missing=print", target],
+        false,
+    )
+    .map_err(|e| format!("Can't find missing objects: {}", e))?;
+    let missing: Vec<&str> = missing.lines().filter_map(|line| line.strip_prefix('?')).collect();
+
+    let mut wanted: Vec<&str> = listing.lines()
+        .filter_map(|line| {
+            let (meta, path) = line.split_once('\t')?;
+            let mut fields = meta.split_whitespace();
+            let (kind, oid) = (fields.nth(1)?, fields.next()?);
+            (kind == "blob" && in_sparse_cone(path, sparse_paths) && missing.contains(&oid)).then_some(oid)
+        })
+        .collect();
+    wanted.sort_unstable();
+    wanted.dedup();
+
+    if wanted.is_empty() {
+        return Ok(());
+    }
+
+    println!("Prefetching {} objects for the offline checkout...", wanted.len());
+    let filter_arg = format!("--filter={}", filter);
+    for chunk in wanted.chunks(1000) {
+        let mut args = vec![
+            "-c", "fetch.negotiationAlgorithm=noop",
+            "fetch", "--quiet", "--no-tags", "--no-write-fetch-head", &filter_arg, "origin",
+        ];
+        args.extend(chunk);
+        security::run_sandboxed_git(repo_path, &args, true)
+            .map_err(|e| format!("Prefetch failed: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Whether a cone mode sparse checkout of `sparse_paths` includes the file at `path`:
+/// everything under a listed directory, plus files directly inside any of its parents
+fn in_sparse_cone(path: &str, sparse_paths: &[String]) -> bool {
+    if sparse_paths.is_empty() {
+        return true;
+    }
+
+    let parent = path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+    sparse_paths.iter().any(|dir| {
+        let dir = dir.trim_matches('/');
+        path.starts_with(&format!("{}/", dir))
+            || parent.is_empty()
+            || dir == parent
+            || dir.starts_with(&format!("{}/", parent))
+    })
+}
+
+/// Replace LFS pointer files left by the offline checkout with verified content,
+/// or just say they're there when LFS downloads weren't asked for
+pub(crate) fn fetch_lfs_objects(repo_path: &Path, repo_url: &str, max_size_mb: Option<u64>) -> Result<(), String> {
+    if !lfs::uses_lfs(repo_path) {
+        return Ok(());
+    }
+    
+    let Some(max_size_mb) = max_size_mb else {
+        let pointers = lfs::find_pointers(repo_path).len();
+        if pointers > 0 {
+            println!("\n⚠️  {} LFS files are pointers only (use --lfs to download them)", pointers);
+        }
+        return Ok(());
+    };
+    
+    println!("\n{}", "=".repeat(60));
+    println!("LFS OBJECTS (sandboxed, with network, verified against pointers)");
+    println!("{}", "=".repeat(60));
+    
+    let report = lfs::materialize(repo_path, repo_url, max_size_mb * 1024 * 1024)?;
+    println!("✓ {} LFS files materialized", report.materialized);
+    if !report.skipped.is_empty() {
+        println!("⚠️  {} over {} MB left as pointers:", report.skipped.len(), max_size_mb);
+        for path in &report.skipped {
+            println!("  {}", path);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Sandbox side of `fetch_lfs_objects`, see `lfs::fetch_requested`
+pub fn lfs_fetch(endpoint: &str) {
+    if let Err(e) = lfs::fetch_requested(endpoint) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Resolve a tag, remote branch or commit inside the sandbox without network
+pub(crate) fn resolve_requested_ref(repo_path: &Path, git_ref: &str) -> Option<String> {
+    let candidates = [
+        format!("refs/tags/{}", git_ref),
+        format!("refs/remotes/origin/{}", git_ref),
+        git_ref.to_string(),
+    ];
+    
+    candidates.iter().find_map(|candidate| {
+        let spec = format!("{}^{{commit}}", candidate);
+        security::sandboxed_git_output(repo_path, &["rev-parse", "--verify", "--quiet", &spec], false)
+            .ok()
+            .filter(|commit| !commit.is_empty())
+    })
+}
+
+pub(crate) fn is_full_sha(git_ref: &str) -> bool {
+    (git_ref.len() == 40 || git_ref.len() == 64) && git_ref.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Run the suspicious pattern scanner and print a short report. Submodules are only
+/// scanned when the submodule policy fetched them.
+pub(crate) fn security_scan(repo_path: &Path, with_submodules: bool) -> Vec<String> {
+    println!("\n{}", "=".repeat(60));
+    println!("SECURITY SCAN");
+    println!("{}", "=".repeat(60));
+    
+    let warnings = security::scan_for_suspicious_patterns(repo_path.to_str().unwrap(), with_submodules);
+    
+    if !warnings.is_empty() {
+        println!("⚠️  {} suspicious patterns detected:", warnings.len());
+        for (i, warning) in warnings.iter().take(5).enumerate() {
+            println!("  {}. {}", i + 1, warning);
+        }
+        if warnings.len() > 5 {
+            println!("  ... and {} more", warnings.len() - 5);
+        }
+    } else {
+        println!("No obvious threats detected.");
+    }
+    
+    warnings
+}
+
+/// Recursively copy a checked-out repository to its install location
+pub(crate) fn copy_dir(src: &Path, dest: &Path) -> bool {
+    Command::new("cp")
+        .args(["-r", src.to_str().unwrap(), dest.to_str().unwrap()])
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/// Swap the installed copy for the freshly checked-out workspace
+pub(crate) fn replace_installed_copy(src: &Path, dest: &Path) -> Result<(), String> {
+    let file_name = dest.file_name()
+        .ok_or("Invalid install path")?
+        .to_string_lossy();
+    let staging = dest.with_file_name(format!(".{}.gitfetch-new", file_name));
+
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| format!("Can't clear staging copy: {}", e))?;
+    }
+
+    if !copy_dir(src, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Failed to copy to {}", staging.display()));
+    }
+
+    if dest.exists() {
+        fs::remove_dir_all(dest).map_err(|e| format!("Can't remove old copy: {}", e))?;
+    }
+
+    fs::rename(&staging, dest).map_err(|e| format!("Can't install new copy: {}", e))
 }
@@ -0,0 +1,283 @@
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::security;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Oldest git that honours `GIT_CONFIG_COUNT`, which is how hooks get disabled
+const MIN_GIT_VERSION: (u32, u32) = (2, 31);
+
+#[derive(Default)]
+struct Report {
+    warnings: usize,
+    failures: usize,
+}
+
+impl Report {
+    fn pass(&mut self, message: &str) {
+        println!("  ✓ {}", message);
+    }
+
+    fn warn(&mut self, message: &str) {
+        println!("  ⚠️  {}", message);
+        self.warnings += 1;
+    }
+
+    fn fail(&mut self, message: &str) {
+        println!("  ✗ {}", message);
+        self.failures += 1;
+    }
+}
+
+pub fn doctor() {
+    let mut report = Report::default();
+
+    section("ENVIRONMENT");
+    let have_bwrap = check_tool(&mut report, "bwrap");
+    let have_timeout = check_tool(&mut report, "timeout");
+    check_git_version(&mut report);
+    check_ro_binds(&mut report);
+    check_user_namespaces(&mut report);
+
+    section("SANDBOX");
+    if have_bwrap && have_timeout {
+        check_sandbox(&mut report);
+    } else {
+        report.fail("skipped, bwrap and timeout are both required");
+    }
+
+    section("STATE");
+    check_state(&mut report);
+
+    println!("\n{}", "=".repeat(60));
+    println!("{} problems, {} warnings", report.failures, report.warnings);
+
+    if report.failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn section(title: &str) {
+    println!("\n{}", "=".repeat(60));
+    println!("{}", title);
+    println!("{}", "=".repeat(60));
+}
+
+fn check_tool(report: &mut Report, tool: &str) -> bool {
+    let found = Command::new("which")
+        .arg(tool)
+        .output()
+        .is_ok_and(|output| output.status.success());
+
+    if found {
+        report.pass(&format!("{} found", tool));
+    } else {
+        report.fail(&format!("{} not found on PATH", tool));
+    }
+    found
+}
+
+fn check_git_version(report: &mut Report) {
+    let output = match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => output,
+        _ => {
+            report.fail("git not found on PATH");
+            return;
+        }
+    };
+
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut numbers = version
+        .trim_start_matches("git version ")
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse::<u32>().ok());
+
+    match (numbers.next(), numbers.next()) {
+        (Some(major), Some(minor)) if (major, minor) >= MIN_GIT_VERSION => report.pass(&version),
+        (Some(_), Some(_)) => report.fail(&format!(
+            "{} is too old, hooks can only be disabled from {}.{} on",
+            version, MIN_GIT_VERSION.0, MIN_GIT_VERSION.1
+        )),
+        _ => report.warn(&format!("Can't parse git version: {}", version)),
+    }
+}
+
+fn check_ro_binds(report: &mut Report) {
+    let missing: Vec<&str> = security::SANDBOX_RO_BINDS.iter()
+        .copied()
+        .filter(|dir| !Path::new(dir).exists())
+        .collect();
+
+    if missing.is_empty() {
+        report.pass("All sandbox mount points exist");
+    } else {
+        report.fail(&format!("Missing sandbox mount points: {}", missing.join(", ")));
+    }
+}
+
+fn check_user_namespaces(report: &mut Report) {
+    let read = |path: &str| fs::read_to_string(path).ok().map(|value| value.trim().to_string());
+
+    if read("/proc/sys/kernel/unprivileged_userns_clone").as_deref() == Some("0") {
+        report.fail("Unprivileged user namespaces are disabled (kernel.unprivileged_userns_clone = 0)");
+    } else if read("/proc/sys/user/max_user_namespaces").as_deref() == Some("0") {
+        report.fail("User namespaces are disabled (user.max_user_namespaces = 0)");
+    } else if read("/proc/sys/kernel/apparmor_restrict_unprivileged_userns").as_deref() == Some("1") {
+        report.warn("AppArmor restricts unprivileged user namespaces, bwrap needs a profile or setuid");
+    } else {
+        report.pass("User namespaces allowed");
+    }
+}
+
+/// Launch real sandboxes and check that the isolation they promise holds
+fn check_sandbox(report: &mut Report) {
+    let scratch = std::env::temp_dir().join(format!("gitfetch-doctor-{}", std::process::id()));
+    if let Err(e) = fs::create_dir_all(&scratch) {
+        report.fail(&format!("Can't create scratch dir: {}", e));
+        return;
+    }
+
+    match security::sandboxed_probe(&scratch, &["git", "--version"], false) {
+        Ok(_) => report.pass("Sandbox starts and runs git"),
+        Err(e) => {
+            report.fail(&format!("Sandbox failed to start: {}", e));
+            let _ = fs::remove_dir_all(&scratch);
+            return;
+        }
+    }
+
+    match security::sandboxed_probe(&scratch, &["cat", "/proc/net/dev"], false) {
+        Ok(devices) => {
+            let interfaces: Vec<&str> = devices.lines()
+                .filter_map(|line| line.split_once(':').map(|(name, _)| name.trim()))
+                .filter(|name| *name != "lo")
+                .collect();
+            if interfaces.is_empty() {
+                report.pass("Network isolated during checkout");
+            } else {
+                report.fail(&format!("Offline sandbox can see network interfaces: {}", interfaces.join(", ")));
+            }
+        }
+        Err(e) => report.fail(&format!("Can't inspect sandbox network: {}", e)),
+    }
+
+    match security::sandboxed_probe(&scratch, &["git", "config", "--get", "core.hooksPath"], false) {
+        Ok(hooks) if hooks == "/dev/null" => report.pass("Git hooks disabled"),
+        Ok(hooks) => report.fail(&format!("Git hooks path is {}, not /dev/null", hooks)),
+        Err(e) => report.fail(&format!("Git hooks not disabled: {}", e)),
+    }
+
+    let config_dir = GitFetchConfig::config_path()
+        .parent()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if Path::new(&config_dir).exists() {
+        match security::sandboxed_probe(&scratch, &["test", "-e", &config_dir], false) {
+            Ok(_) => report.fail(&format!("{} is visible inside the sandbox", config_dir)),
+            Err(_) => report.pass("Home directory hidden from the sandbox"),
+        }
+    }
+
+    match security::sandboxed_probe(&scratch, &["touch", "/usr/.gitfetch-doctor"], false) {
+        Ok(_) => {
+            let _ = fs::remove_file("/usr/.gitfetch-doctor");
+            report.fail("System directories are writable from the sandbox");
+        }
+        Err(_) => report.pass("System directories read-only"),
+    }
+
+    let _ = fs::remove_dir_all(&scratch);
+}
+
+fn check_state(report: &mut Report) {
+    let config_path = GitFetchConfig::config_path();
+    if !config_path.exists() {
+        report.pass("No config yet, nothing installed");
+        return;
+    }
+
+    let contents = match fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.fail(&format!("Can't read {}: {}", config_path.display(), e));
+            return;
+        }
+    };
+
+    // Parse by hand, load() falls back to an empty config and the next save would wipe it
+    let config: GitFetchConfig = match serde_json::from_str(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            report.fail(&format!("{} doesn't parse: {}", config_path.display(), e));
+            return;
+        }
+    };
+    report.pass(&format!("{} parses", config_path.display()));
+
+    let workspace_root = GitFetchConfig::workspace_root();
+    let mut seen = HashSet::new();
+
+    for repo in &config.installed_repos {
+        let label = if repo.id.is_empty() { &repo.name } else { &repo.id };
+        let before = report.failures + report.warnings;
+
+        if repo.id.is_empty() {
+            report.warn(&format!("{}: legacy entry without an id, run any command to migrate", label));
+        } else if repo.id != git::repo_id(&repo.url) {
+            report.warn(&format!("{}: id doesn't match url {}", label, repo.url));
+        }
+
+        if !seen.insert(label.to_string()) {
+            report.fail(&format!("{}: recorded more than once", label));
+        }
+
+        if !Path::new(&repo.path).exists() {
+            report.fail(&format!("{}: install path {} is missing", label, repo.path));
+        } else if let Some(recorded) = &repo.commit_hash {
+            match git::get_commit_hash(&repo.path) {
+                Some(on_disk) if &on_disk == recorded => {}
+                Some(on_disk) => report.warn(&format!(
+                    "{}: checked out {:.8} but recorded {:.8}", label, on_disk, recorded
+                )),
+                None => report.warn(&format!("{}: {} is not a git checkout", label, repo.path)),
+            }
+        }
+
+        if let Some(workspace) = &repo.workspace_path {
+            if !Path::new(workspace).exists() {
+                report.warn(&format!("{}: workspace {} is missing", label, workspace));
+            } else if !security::is_within_root(Path::new(workspace), &workspace_root) {
+                report.fail(&format!("{}: workspace {} is outside {}", label, workspace, workspace_root.display()));
+            }
+        }
+
+        for version in &repo.history {
+            if !Path::new(&version.path).exists() {
+                report.warn(&format!("{}: saved version {:.8} is missing", label, version.commit_hash));
+            }
+        }
+
+        if report.failures + report.warnings == before {
+            report.pass(label);
+        }
+    }
+
+    for (url, checksum) in &config.checksum_registry {
+        let valid_hash = checksum.total_hash.len() == 64
+            && checksum.total_hash.chars().all(|c| c.is_ascii_hexdigit());
+
+        if &checksum.repo_url != url {
+            report.fail(&format!("Registry entry {} is recorded for {}", url, checksum.repo_url));
+        } else if !valid_hash {
+            report.fail(&format!("Registry entry {} has a malformed total hash", url));
+        } else if checksum.file_checksums.is_empty() {
+            report.warn(&format!("Registry entry {} covers no files", url));
+        }
+    }
+
+    if !config.checksum_registry.is_empty() {
+        println!("  {} registry entries checked", config.checksum_registry.len());
+    }
+}
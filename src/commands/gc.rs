@@ -0,0 +1,179 @@
+use crate::config::GitFetchConfig;
+use crate::mirror;
+use crate::security;
+use super::info::{dir_size, format_size};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    reason: String,
+    /// Installed repo whose workspace this is, if any
+    owner: Option<String>,
+}
+
+pub fn gc_workspaces(dry_run: bool, max_size_mb: Option<u64>, yes: bool) {
+    let config = GitFetchConfig::load();
+    let workspace_root = GitFetchConfig::workspace_root();
+    let versions_root = GitFetchConfig::versions_root();
+    let cache_root = GitFetchConfig::cache_root();
+
+    let mut candidates = Vec::new();
+
+    let live_workspaces: Vec<PathBuf> = config.installed_repos.iter()
+        .filter_map(|repo| repo.workspace_path.as_ref().map(PathBuf::from))
+        .collect();
+
+    for dir in find_orphans(&workspace_root, &live_workspaces) {
+        candidates.push(Candidate {
+            size: dir_size(&dir),
+            path: dir,
+            reason: "orphaned".to_string(),
+            owner: None,
+        });
+    }
+
+    if let Some(limit) = max_size_mb {
+        for repo in &config.installed_repos {
+            let Some(dir) = repo.workspace_path.as_ref().map(PathBuf::from) else { continue };
+            // Never delete the only copy of an install kept inside its workspace
+            if !dir.exists() || Path::new(&repo.path).starts_with(&dir) {
+                continue;
+            }
+            let size = dir_size(&dir);
+            if size > limit * 1024 * 1024 {
+                candidates.push(Candidate {
+                    path: dir,
+                    size,
+                    reason: format!("over {} MB", limit),
+                    owner: Some(repo.id.clone()),
+                });
+            }
+        }
+    }
+
+    let live_versions: Vec<PathBuf> = config.installed_repos.iter()
+        .map(|repo| versions_root.join(&repo.id))
+        .collect();
+
+    for dir in find_orphans(&versions_root, &live_versions) {
+        candidates.push(Candidate {
+            size: dir_size(&dir),
+            path: dir,
+            reason: "saved versions of a removed repo".to_string(),
+            owner: None,
+        });
+    }
+
+    let live_mirrors: Vec<PathBuf> = config.installed_repos.iter()
+        .map(|repo| mirror::mirror_path(&repo.url))
+        .collect();
+
+    for dir in find_orphans(&cache_root, &live_mirrors) {
+        candidates.push(Candidate {
+            size: dir_size(&dir),
+            path: dir,
+            reason: "cache of a removed repo".to_string(),
+            owner: None,
+        });
+    }
+
+    if candidates.is_empty() {
+        println!("Nothing to clean up.");
+        return;
+    }
+
+    let total: u64 = candidates.iter().map(|candidate| candidate.size).sum();
+
+    println!("\n{}", "=".repeat(60));
+    println!("WORKSPACE GARBAGE");
+    println!("{}", "=".repeat(60));
+    for candidate in &candidates {
+        println!("  {:>10}  {} ({})", format_size(candidate.size), candidate.path.display(), candidate.reason);
+    }
+    println!("\nTotal: {}", format_size(total));
+
+    if dry_run {
+        println!("\nDry run, nothing deleted.");
+        return;
+    }
+
+    if !yes && !security::prompt_user("\nDelete these directories? (yes/no)") {
+        println!("GC cancelled.");
+        return;
+    }
+
+    let mut config = GitFetchConfig::load();
+    let mut freed = 0;
+    let mut failed = false;
+
+    for candidate in &candidates {
+        let root = [&versions_root, &cache_root].into_iter()
+            .find(|root| candidate.path.starts_with(root))
+            .unwrap_or(&workspace_root);
+        if !security::is_within_root(&candidate.path, root) {
+            eprintln!("Refusing to delete {}: outside {}", candidate.path.display(), root.display());
+            failed = true;
+            continue;
+        }
+
+        match fs::remove_dir_all(&candidate.path) {
+            Ok(()) => {
+                freed += candidate.size;
+                if let Some(owner) = &candidate.owner {
+                    config.set_workspace_path(owner, None);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to delete {}: {}", candidate.path.display(), e);
+                failed = true;
+            }
+        }
+    }
+
+    println!("\n✓ Freed {}", format_size(freed));
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Directories under `root` that neither are nor contain one of the live directories
+fn find_orphans(root: &Path, live: &[PathBuf]) -> Vec<PathBuf> {
+    let mut orphans = Vec::new();
+
+    for dir in subdirectories(root) {
+        if live.iter().any(|live_dir| same_path(live_dir, &dir)) {
+            continue;
+        }
+        // host/ and host/owner/ levels hold other repos' workspaces
+        if live.iter().any(|live_dir| live_dir.starts_with(&dir)) {
+            orphans.extend(find_orphans(&dir, live));
+        } else {
+            orphans.push(dir);
+        }
+    }
+
+    orphans
+}
+
+fn subdirectories(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+        .map(|entries| {
+            entries.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
@@ -0,0 +1,141 @@
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::mirror;
+use std::fs;
+use std::path::Path;
+
+pub fn repo_info(name: &str) {
+    let config = GitFetchConfig::load();
+
+    let repo = match config.resolve_repo(name) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let path_exists = Path::new(&repo.path).exists();
+
+    println!("\n{}", "=".repeat(60));
+    println!("{}", repo.id);
+    println!("{}", "=".repeat(60));
+    println!("URL:        {}", repo.url);
+    println!("Path:       {}{}", repo.path, if path_exists { "" } else { " (MISSING)" });
+
+    match &repo.workspace_path {
+        Some(workspace) if Path::new(workspace).exists() => {
+            println!("Workspace:  {} ({})", workspace, format_size(dir_size(Path::new(workspace))));
+        }
+        Some(workspace) => println!("Workspace:  {} (MISSING)", workspace),
+        None => println!("Workspace:  none"),
+    }
+
+    if let Some(cache) = mirror::existing(&repo.url) {
+        println!("Cache:      {} ({})", cache.display(), format_size(dir_size(&cache)));
+    }
+
+    if let Some(git_ref) = &repo.git_ref {
+        println!("Ref:        {}", git_ref);
+    }
+    if let Some(depth) = repo.depth {
+        println!("Depth:      {}", depth);
+    }
+    if let Some(filter) = &repo.filter {
+        println!("Filter:     {}", filter);
+    }
+    if !repo.sparse_paths.is_empty() {
+        println!("Sparse:     {}", repo.sparse_paths.join(", "));
+    }
+    if let Some(policy) = &repo.submodules {
+        println!("Submodules: {}", policy);
+    }
+    if let Some(max_size) = repo.lfs_max_size {
+        println!("LFS:        downloaded, up to {} MB per file", max_size);
+    }
+    if repo.archive {
+        println!("Mode:       archive (no git history)");
+    }
+
+    let recorded = repo.commit_hash.as_deref().unwrap_or("unknown");
+    println!("Commit:     {}", recorded);
+
+    if path_exists {
+        match git::get_commit_hash(&repo.path) {
+            Some(on_disk) if Some(on_disk.as_str()) == repo.commit_hash.as_deref() => {
+                println!("On disk:    {} (matches)", on_disk);
+            }
+            Some(on_disk) => println!("On disk:    {} (DIFFERS from recorded)", on_disk),
+            None => println!("On disk:    not a git checkout"),
+        }
+    }
+
+    println!("Verified:   {}", if repo.verified { "yes" } else { "no" });
+
+    println!("\nChecksum registry:");
+    match config.get_checksum(&repo.url) {
+        Some(checksum) => {
+            println!("  Commit:      {}", checksum.commit_hash);
+            if Some(checksum.commit_hash.as_str()) != repo.commit_hash.as_deref() {
+                println!("               (recorded for a different commit than installed)");
+            }
+            println!("  Files:       {}", checksum.file_checksums.len());
+            println!("  Total hash:  {}", checksum.total_hash);
+            println!("  Verified at: {}", checksum.verified_at);
+        }
+        None => println!("  none (create one with: gitfetch checksum {} --save)", repo.path),
+    }
+
+    if !repo.history.is_empty() {
+        println!("\nSaved versions:");
+        for version in repo.history.iter().rev() {
+            println!("  {:.12}  {}", version.commit_hash, version.saved_at);
+        }
+    }
+
+    println!("\nLast scan:");
+    match &repo.scan_warnings {
+        Some(warnings) if warnings.is_empty() => println!("  No obvious threats detected."),
+        Some(warnings) => {
+            println!("  ⚠️  {} suspicious patterns:", warnings.len());
+            for warning in warnings {
+                println!("    {}", warning);
+            }
+        }
+        None => println!("  never scanned"),
+    }
+}
+
+/// Total size of all files under a directory, not following symlinks
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let mut total = 0;
+
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => total += dir_size(&entry.path()),
+                Ok(metadata) => total += metadata.len(),
+                Err(_) => {}
+            }
+        }
+    }
+
+    total
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -3,20 +3,36 @@ use crate::config::GitFetchConfig;
 pub fn list_repos() {
     let config = GitFetchConfig::load();
     
-    if config.installed_repos.is_empty() {
+    if config.installed_repos.is_empty() && config.installed_releases.is_empty() {
         println!("No repositories installed yet.");
         return;
     }
 
-    println!("Installed repositories:\n");
-    for repo in &config.installed_repos {
-        let marker = if repo.verified { "✓" } else { "?" };
-        println!("  {} {}", marker, repo.name);
-        println!("    {}", repo.url);
-        println!("    {}", repo.path);
-        if let Some(commit) = &repo.commit_hash {
-            println!("    Commit: {:.8}", commit);
+    if !config.installed_repos.is_empty() {
+        println!("Installed repositories:\n");
+        for repo in &config.installed_repos {
+            let marker = if repo.verified { "✓" } else { "?" };
+            println!("  {} {}", marker, repo.id);
+            println!("    {}", repo.url);
+            println!("    {}", repo.path);
+            if let Some(commit) = &repo.commit_hash {
+                println!("    Commit: {:.8}", commit);
+            }
+            if let Some(git_ref) = &repo.git_ref {
+                println!("    Ref:    {}", git_ref);
+            }
+            println!();
+        }
+    }
+
+    if !config.installed_releases.is_empty() {
+        println!("Installed releases:\n");
+        for release in &config.installed_releases {
+            let marker = if release.verified_by != "none" { "✓" } else { "?" };
+            println!("  {} {} {}", marker, release.id, release.tag);
+            println!("    {}", release.path);
+            println!("    SHA-256: {:.16} ({})", release.sha256, release.verified_by);
+            println!();
         }
-        println!();
     }
 }
@@ -0,0 +1,164 @@
+use crate::checksum;
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::security;
+use crate::types::{InstalledRepo, LockFile, LockedRepo, LOCKFILE_VERSION};
+use super::clone::{install_repo, CloneOptions};
+use std::fs;
+use std::path::Path;
+
+pub fn lock_export(output: &str) {
+    let config = GitFetchConfig::load();
+
+    if config.installed_repos.is_empty() {
+        println!("No repositories installed yet.");
+        return;
+    }
+
+    let mut repos = Vec::new();
+    let mut failed = false;
+
+    for repo in &config.installed_repos {
+        match lock_entry(&config, repo) {
+            Ok(entry) => repos.push(entry),
+            Err(e) => {
+                eprintln!("✗ {}: {}", repo.id, e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        eprintln!("\nLockfile not written, fix the repositories above first.");
+        std::process::exit(1);
+    }
+
+    let lock = LockFile {
+        version: LOCKFILE_VERSION,
+        repos,
+    };
+
+    let contents = serde_json::to_string_pretty(&lock)
+        .expect("Failed to serialize lockfile");
+
+    if let Err(e) = fs::write(output, contents + "\n") {
+        eprintln!("Can't write {}: {}", output, e);
+        std::process::exit(1);
+    }
+
+    println!("✓ Locked {} repositories to {}", lock.repos.len(), output);
+}
+
+/// Pin one installed repo, reusing the registry hash when it matches the commit
+fn lock_entry(config: &GitFetchConfig, repo: &InstalledRepo) -> Result<LockedRepo, String> {
+    let commit = repo.commit_hash.clone()
+        .or_else(|| git::get_commit_hash(&repo.path))
+        .ok_or("No recorded commit")?;
+
+    let total_hash = match config.get_checksum(&repo.url) {
+        Some(registered) if registered.commit_hash == commit => registered.total_hash.clone(),
+        _ => {
+            if !Path::new(&repo.path).exists() {
+                return Err(format!("Path missing and no registry checksum: {}", repo.path));
+            }
+            let calculated = checksum::calculate_repo_checksums(&repo.path)
+                .map_err(|e| format!("Failed to calculate checksums: {}", e))?;
+            if calculated.commit_hash != commit {
+                return Err(format!(
+                    "Checkout is at {:.8} but config records {:.8}",
+                    calculated.commit_hash, commit
+                ));
+            }
+            calculated.total_hash
+        }
+    };
+
+    Ok(LockedRepo {
+        name: repo.id.clone(),
+        url: repo.url.clone(),
+        git_ref: repo.git_ref.clone(),
+        commit,
+        total_hash,
+        submodules: repo.submodules.clone(),
+        lfs_max_size: repo.lfs_max_size,
+        sparse_paths: repo.sparse_paths.clone(),
+        archive: repo.archive,
+    })
+}
+
+pub fn lock_install(file: &str, trust_mode: &str) {
+    security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
+
+    let contents = fs::read_to_string(file).unwrap_or_else(|e| {
+        eprintln!("Can't read {}: {}", file, e);
+        std::process::exit(1);
+    });
+
+    let lock: LockFile = serde_json::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("Invalid lockfile {}: {}", file, e);
+        std::process::exit(1);
+    });
+
+    if lock.version > LOCKFILE_VERSION {
+        eprintln!(
+            "Lockfile version {} is newer than this gitfetch supports ({})",
+            lock.version, LOCKFILE_VERSION
+        );
+        std::process::exit(1);
+    }
+
+    let mut failed = Vec::new();
+
+    for entry in &lock.repos {
+        let config = GitFetchConfig::load();
+        let already_installed = config.find_repo(&git::repo_id(&entry.url)).is_some_and(|repo| {
+            repo.commit_hash.as_deref() == Some(entry.commit.as_str())
+                && Path::new(&repo.path).exists()
+                && config.get_checksum(&repo.url)
+                    .is_some_and(|registered| registered.total_hash == entry.total_hash)
+        });
+
+        if already_installed {
+            println!("✓ {} already at {:.8}", entry.name, entry.commit);
+            continue;
+        }
+
+        let options = CloneOptions {
+            trust_mode,
+            git_ref: entry.git_ref.as_deref(),
+            commit: Some(&entry.commit),
+            expected_total_hash: Some(&entry.total_hash),
+            submodules: entry.submodules.as_deref(),
+            lfs_max_size: entry.lfs_max_size,
+            sparse_paths: &entry.sparse_paths,
+            archive: entry.archive,
+            ..Default::default()
+        };
+
+        match install_repo(&entry.url, &options) {
+            Ok(Some(installed)) if installed.commit_hash.as_deref() == Some(entry.commit.as_str()) => {}
+            Ok(Some(installed)) => {
+                eprintln!(
+                    "✗ {}: installed {:.8}, lockfile wants {:.8}",
+                    entry.name,
+                    installed.commit_hash.as_deref().unwrap_or("unknown"),
+                    entry.commit
+                );
+                failed.push(entry.name.clone());
+            }
+            Ok(None) => failed.push(entry.name.clone()),
+            Err(e) => {
+                eprintln!("✗ {}: {}", entry.name, e);
+                failed.push(entry.name.clone());
+            }
+        }
+    }
+
+    println!("\n{}", "=".repeat(60));
+    if failed.is_empty() {
+        println!("✓ {} repositories match {}", lock.repos.len(), file);
+    } else {
+        println!("✗ {} of {} repositories failed: {}", failed.len(), lock.repos.len(), failed.join(", "));
+        std::process::exit(1);
+    }
+}
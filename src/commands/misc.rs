@@ -1,4 +1,5 @@
 use crate::config::GitFetchConfig;
+use crate::forge;
 use clap::CommandFactory;
 
 use clap_complete::{generate, Shell};
@@ -58,26 +59,36 @@ pub fn complete_suggestions(completion_type: &str, partial: &str) {
         "repos" => {
             let config = GitFetchConfig::load();
             for repo in &config.installed_repos {
-                if repo.name.starts_with(partial) || partial.is_empty() {
+                // Offer the short name only while it is unambiguous
+                let unique = config.installed_repos.iter()
+                    .filter(|other| other.name == repo.name)
+                    .count() == 1;
+                if unique && repo.name.starts_with(partial) {
                     println!("{}", repo.name);
                 }
+                if repo.id.starts_with(partial) {
+                    println!("{}", repo.id);
+                }
             }
         }
         "clone-targets" => {
             let config = GitFetchConfig::load();
             for repo in &config.installed_repos {
-                if repo.url.contains(partial) || repo.name.contains(partial) || partial.is_empty() {
-                    if repo.url.contains("github.com") {
-                        if let Some(path) = repo.url.strip_prefix("https://github.com/") {
-                            let short = path.trim_end_matches(".git");
-                            if short.contains(partial) || partial.is_empty() {
-                                println!("{}", short);
-                            }
-                        }
+                if let Some(short) = forge::shorthand(&config, &repo.url) {
+                    if short.contains(partial) || partial.is_empty() {
+                        println!("{}", short);
                     }
                 }
             }
         }
+        "forges" => {
+            let config = GitFetchConfig::load();
+            for (name, _) in forge::profiles(&config) {
+                if name.starts_with(partial) {
+                    println!("{}", name);
+                }
+            }
+        }
         _ => {}
     }
 }
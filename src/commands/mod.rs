@@ -3,9 +3,34 @@ pub mod list;
 pub mod search;
 pub mod checksum;
 pub mod misc;
+pub mod update;
+pub mod remove;
+pub mod lock;
+pub mod sync;
+pub mod rollback;
+pub mod outdated;
+pub mod info;
+pub mod gc;
+pub mod doctor;
+pub mod adopt;
+pub mod batch;
+pub mod release;
+pub mod auth;
 
-pub use clone::clone_repo;
+pub use clone::{clone_repo, lfs_fetch};
 pub use list::list_repos;
 pub use search::search_repos;
 pub use checksum::{checksum_command, verify_command};
 pub use misc::{easter_egg, generate_completions, complete_suggestions};
+pub use update::update_repos;
+pub use remove::remove_repo;
+pub use lock::{lock_export, lock_install};
+pub use sync::sync_repos;
+pub use rollback::rollback_repo;
+pub use outdated::outdated_repos;
+pub use info::repo_info;
+pub use gc::gc_workspaces;
+pub use doctor::doctor;
+pub use adopt::adopt_repo;
+pub use release::install_release;
+pub use auth::{auth_login, auth_logout, auth_status};
@@ -0,0 +1,239 @@
+use crate::git;
+use crate::security;
+use crate::types::InstalledRepo;
+use super::batch;
+use super::clone::is_full_sha;
+use super::update::resolve_targets;
+use serde::Serialize;
+use std::fs;
+
+#[derive(Serialize, Debug)]
+struct OutdatedStatus {
+    name: String,
+    url: String,
+    /// Remote ref that was compared, e.g. `refs/heads/main`
+    remote_ref: Option<String>,
+    installed_commit: Option<String>,
+    remote_commit: Option<String>,
+    /// `up-to-date`, `outdated`, `pinned` or `error`
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub fn outdated_repos(names: &[String], jobs: usize, json: bool) {
+    security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
+
+    let targets = resolve_targets(names, names.is_empty());
+
+    if targets.is_empty() {
+        if json {
+            println!("[]");
+        } else {
+            println!("No repositories installed yet.");
+        }
+        return;
+    }
+
+    let results = batch::run_parallel(&targets, jobs, check_repo);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results).expect("Failed to serialize results"));
+    } else {
+        print_table(&results);
+    }
+
+    if results.iter().any(|result| result.status == "error") {
+        std::process::exit(1);
+    }
+}
+
+fn check_repo(repo: &InstalledRepo) -> OutdatedStatus {
+    let mut status = OutdatedStatus {
+        name: repo.id.clone(),
+        url: repo.url.clone(),
+        remote_ref: None,
+        installed_commit: repo.commit_hash.clone(),
+        remote_commit: None,
+        status: "error",
+        error: None,
+    };
+
+    // A pinned commit can't move, no need to ask the remote
+    if let Some(git_ref) = repo.git_ref.as_deref().filter(|git_ref| is_full_sha(git_ref)) {
+        status.remote_ref = Some(git_ref.to_string());
+        status.remote_commit = Some(git_ref.to_string());
+        status.status = "pinned";
+        return status;
+    }
+
+    match ls_remote(&repo.url) {
+        Ok(refs) => match pick_ref(&refs, repo.git_ref.as_deref()) {
+            Some((remote_ref, commit)) => {
+                status.status = if repo.commit_hash.as_deref() == Some(commit.as_str()) {
+                    "up-to-date"
+                } else {
+                    "outdated"
+                };
+                status.remote_ref = Some(remote_ref);
+                status.remote_commit = Some(commit);
+            }
+            None => {
+                status.error = Some(format!(
+                    "{} not found on remote",
+                    repo.git_ref.as_deref().unwrap_or("HEAD")
+                ));
+            }
+        },
+        Err(e) => status.error = Some(e),
+    }
+
+    status
+}
+
+/// List remote refs from inside the sandbox, using a throwaway empty directory
+pub(crate) fn ls_remote(url: &str) -> Result<Vec<(String, String)>, String> {
+    let scratch = std::env::temp_dir()
+        .join(format!("gitfetch-ls-remote-{}-{}", std::process::id(), git::repo_id(url).replace('/', "_")));
+    fs::create_dir_all(&scratch).map_err(|e| format!("Can't create scratch dir: {}", e))?;
+
+    let output = security::sandboxed_fetch_output(&scratch, url, &["ls-remote", "--symref", url]);
+    let _ = fs::remove_dir_all(&scratch);
+
+    let refs = output?
+        .lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once('\t')?;
+            Some((left.to_string(), right.to_string()))
+        })
+        .collect();
+
+    Ok(refs)
+}
+
+/// Find the commit for the tracked ref, preferring peeled tags and resolving HEAD to its branch
+pub(crate) fn pick_ref(refs: &[(String, String)], git_ref: Option<&str>) -> Option<(String, String)> {
+    let lookup = |name: &str| {
+        refs.iter()
+            .find(|(_, remote_ref)| remote_ref == name)
+            .map(|(commit, _)| commit.clone())
+    };
+
+    match git_ref {
+        Some(git_ref) => {
+            let tag = format!("refs/tags/{}", git_ref);
+            let branch = format!("refs/heads/{}", git_ref);
+            lookup(&format!("{}^{{}}", tag))
+                .map(|commit| (tag.clone(), commit))
+                .or_else(|| lookup(&tag).map(|commit| (tag.clone(), commit)))
+                .or_else(|| lookup(&branch).map(|commit| (branch.clone(), commit)))
+        }
+        None => {
+            let head_branch = refs.iter()
+                .find(|(left, remote_ref)| left.starts_with("ref: ") && remote_ref == "HEAD")
+                .map(|(left, _)| left.trim_start_matches("ref: ").to_string())
+                .unwrap_or_else(|| "HEAD".to_string());
+            let commit = refs.iter()
+                .find(|(left, remote_ref)| remote_ref == "HEAD" && !left.starts_with("ref: "))
+                .map(|(commit, _)| commit.clone())?;
+            Some((head_branch, commit))
+        }
+    }
+}
+
+fn print_table(results: &[OutdatedStatus]) {
+    let outdated = results.iter().filter(|result| result.status == "outdated").count();
+
+    println!();
+    for result in results {
+        let installed = result.installed_commit.as_deref().unwrap_or("unknown");
+        match result.status {
+            "outdated" => println!(
+                "  ↑ {}  {} {:.8} -> {:.8}",
+                result.name,
+                result.remote_ref.as_deref().unwrap_or("HEAD"),
+                installed,
+                result.remote_commit.as_deref().unwrap_or("unknown")
+            ),
+            "up-to-date" => println!("  ✓ {}  up to date ({:.8})", result.name, installed),
+            "pinned" => println!("  = {}  pinned to {:.8}", result.name, installed),
+            _ => println!(
+                "  ✗ {}  {}",
+                result.name,
+                result.error.as_deref().unwrap_or("unknown error")
+            ),
+        }
+    }
+
+    println!("\n{} of {} repositories outdated", outdated, results.len());
+    if outdated > 0 {
+        println!("💡 Update with: gitfetch update <name> (or --all)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pick_ref;
+
+    fn refs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries.iter().map(|(left, name)| (left.to_string(), name.to_string())).collect()
+    }
+
+    #[test]
+    fn head_resolves_to_its_branch() {
+        let refs = refs(&[
+            ("ref: refs/heads/main", "HEAD"),
+            ("aaa", "HEAD"),
+            ("aaa", "refs/heads/main"),
+        ]);
+        assert_eq!(pick_ref(&refs, None), Some(("refs/heads/main".to_string(), "aaa".to_string())));
+    }
+
+    #[test]
+    fn head_without_symref_is_reported_as_head() {
+        let refs = refs(&[("aaa", "HEAD")]);
+        assert_eq!(pick_ref(&refs, None), Some(("HEAD".to_string(), "aaa".to_string())));
+    }
+
+    #[test]
+    fn missing_head_is_none() {
+        let refs = refs(&[("aaa", "refs/heads/main")]);
+        assert_eq!(pick_ref(&refs, None), None);
+    }
+
+    #[test]
+    fn annotated_tags_are_peeled() {
+        let refs = refs(&[
+            ("tagobject", "refs/tags/v1"),
+            ("commit", "refs/tags/v1^{}"),
+        ]);
+        assert_eq!(pick_ref(&refs, Some("v1")), Some(("refs/tags/v1".to_string(), "commit".to_string())));
+    }
+
+    #[test]
+    fn lightweight_tags_are_used_as_is() {
+        let refs = refs(&[("commit", "refs/tags/v1")]);
+        assert_eq!(pick_ref(&refs, Some("v1")), Some(("refs/tags/v1".to_string(), "commit".to_string())));
+    }
+
+    #[test]
+    fn tags_win_over_branches_of_the_same_name() {
+        let refs = refs(&[
+            ("branch", "refs/heads/release"),
+            ("tag", "refs/tags/release"),
+        ]);
+        assert_eq!(pick_ref(&refs, Some("release")), Some(("refs/tags/release".to_string(), "tag".to_string())));
+    }
+
+    #[test]
+    fn branches_are_found() {
+        let refs = refs(&[("branch", "refs/heads/dev")]);
+        assert_eq!(pick_ref(&refs, Some("dev")), Some(("refs/heads/dev".to_string(), "branch".to_string())));
+    }
+
+    #[test]
+    fn unknown_refs_are_none() {
+        let refs = refs(&[("branch", "refs/heads/dev")]);
+        assert_eq!(pick_ref(&refs, Some("gone")), None);
+    }
+}
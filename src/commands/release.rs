@@ -0,0 +1,337 @@
+use crate::config::GitFetchConfig;
+use crate::forge;
+use crate::git;
+use crate::http;
+use crate::security;
+use crate::types::{GitHubAsset, GitHubRelease, InstalledRelease};
+use super::clone::expand_repo_url;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Asset names that hold checksums for the other assets
+const CHECKSUM_FILES: [&str; 4] = ["SHA256SUMS", "SHA256SUMS.txt", "sha256sums.txt", "checksums.txt"];
+
+pub fn install_release(repo: &str, tag: Option<&str>, asset_pattern: Option<&str>, output: Option<&str>, trust_mode: &str) {
+    let repo_url = expand_repo_url(repo).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let repo_id = git::repo_id(&repo_url);
+
+    let config = GitFetchConfig::load();
+    if !config.is_host_allowed(&repo_url) {
+        eprintln!("{} is not in allowed_hosts", git::repo_host(&repo_url));
+        std::process::exit(1);
+    }
+
+    // Releases are read from the GitHub API of the forge the repo is on
+    let (api, owner_repo) = forge::github_api(&config, &repo_url).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let client = http::client();
+
+    let release_url = match tag {
+        Some(tag) => format!("{}/repos/{}/releases/tags/{}", api.base, owner_repo, urlencoding::encode(tag)),
+        None => format!("{}/repos/{}/releases/latest", api.base, owner_repo),
+    };
+    let release: GitHubRelease = api.fetch_json(&client, &release_url).unwrap_or_else(|e| {
+        eprintln!("Can't get release of {}: {}", repo_id, e);
+        std::process::exit(1);
+    });
+
+    let asset = pick_asset(&release.assets, asset_pattern).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Asset names end up in a path, keep them from escaping the install directory
+    if asset.name.is_empty() || asset.name.contains('/') || asset.name.contains('\\') || asset.name.starts_with('.') {
+        eprintln!("Refusing asset with unsafe name: {}", asset.name);
+        std::process::exit(1);
+    }
+
+    let install_dir = output.map(PathBuf::from).unwrap_or_else(|| {
+        std::env::current_dir().expect("Can't get current directory")
+    });
+    let install_path = install_dir.join(&asset.name);
+    let registry_key = format!("{}@{}/{}", repo_id, release.tag_name, asset.name);
+
+    println!("\n{}", "=".repeat(60));
+    println!("RELEASE: {} {}", repo_id, release.tag_name);
+    println!("{}", "=".repeat(60));
+    println!("Asset:      {} ({} bytes)", asset.name, asset.size);
+    println!("Trust mode: {}", trust_mode);
+
+    println!("\n{}", "=".repeat(60));
+    println!("DOWNLOADING");
+    println!("{}", "=".repeat(60));
+
+    fs::create_dir_all(&install_dir).unwrap_or_else(|e| {
+        eprintln!("Can't create {}: {}", install_dir.display(), e);
+        std::process::exit(1);
+    });
+    let partial = install_dir.join(format!(".{}.part", asset.name));
+    let actual = download(&client, &asset.browser_download_url, &partial).unwrap_or_else(|e| {
+        let _ = fs::remove_file(&partial);
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    println!("SHA-256: {}", actual);
+
+    println!("\n{}", "=".repeat(60));
+    println!("VERIFYING DIGEST");
+    println!("{}", "=".repeat(60));
+
+    // Every published digest has to agree, not just the first one found
+    let mut expected: Vec<(&str, String)> = Vec::new();
+    if let Some(digest) = asset.digest.as_deref().and_then(|digest| digest.strip_prefix("sha256:")) {
+        expected.push(("digest", digest.to_lowercase()));
+    }
+    if let Some(sums) = release.assets.iter().find(|other| CHECKSUM_FILES.contains(&other.name.as_str())) {
+        match fetch_text(&client, &sums.browser_download_url) {
+            Ok(text) => match find_sum(&text, &asset.name) {
+                Some(sum) => expected.push(("SHA256SUMS", sum)),
+                None => println!("⚠️  {} doesn't list {}", sums.name, asset.name),
+            },
+            Err(e) => println!("⚠️  Can't read {}: {}", sums.name, e),
+        }
+    }
+    if let Some(known) = config.release_registry.get(&registry_key) {
+        expected.push(("registry", known.clone()));
+    }
+
+    for (source, sum) in &expected {
+        if *sum != actual {
+            let _ = fs::remove_file(&partial);
+            eprintln!("✗ Digest mismatch against {}\n  expected: {}\n  actual:   {}", source, sum, actual);
+            std::process::exit(1);
+        }
+        println!("✓ Matches {}", source);
+    }
+
+    let verified_by = expected.first().map(|(source, _)| source.to_string()).unwrap_or_else(|| "none".to_string());
+
+    // Paranoid mode: always prompt
+    // Normal mode: prompt unless a published or known digest matched
+    // YOLO mode: never prompt
+    let accepted = match trust_mode {
+        "yolo" => true,
+        "paranoid" => security::prompt_user(&format!("\nInstall {} to {}? (yes/no)", asset.name, install_path.display())),
+        _ => !expected.is_empty() || security::prompt_user(&format!(
+            "\nNo published checksum for {}. Install unverified? (yes/no)", asset.name
+        )),
+    };
+
+    if !accepted {
+        let _ = fs::remove_file(&partial);
+        println!("Install cancelled.");
+        return;
+    }
+
+    if let Err(e) = fs::rename(&partial, &install_path) {
+        let _ = fs::remove_file(&partial);
+        eprintln!("Can't write {}: {}", install_path.display(), e);
+        std::process::exit(1);
+    }
+
+    GitFetchConfig::edit(|config| {
+        // Remember the digest so a later reinstall of this exact asset is checked against it
+        if !config.release_registry.contains_key(&registry_key) {
+            config.add_release_digest(registry_key.clone(), actual.clone());
+        }
+        config.add_release(InstalledRelease {
+            id: repo_id.clone(),
+            tag: release.tag_name.clone(),
+            asset: asset.name.clone(),
+            path: install_path.to_string_lossy().to_string(),
+            sha256: actual.clone(),
+            verified_by: verified_by.clone(),
+            installed_at: chrono::Utc::now().to_rfc3339(),
+        });
+    });
+
+    println!("\n{}", "=".repeat(60));
+    println!("✓ INSTALLED {} {}", repo_id, release.tag_name);
+    println!("{}", "=".repeat(60));
+    println!("Location: {}", install_path.display());
+    if verified_by == "none" {
+        println!("\n⚠️  Not verified, the digest was recorded so reinstalls are checked against it");
+    }
+}
+
+/// Pick the asset matching `pattern` (`*` wildcards), or the only one for this platform
+fn pick_asset<'a>(assets: &'a [GitHubAsset], pattern: Option<&str>) -> Result<&'a GitHubAsset, String> {
+    let installable: Vec<&GitHubAsset> = assets.iter()
+        .filter(|asset| !CHECKSUM_FILES.contains(&asset.name.as_str()) && !asset.name.ends_with(".sha256"))
+        .collect();
+
+    let candidates: Vec<&GitHubAsset> = match pattern {
+        Some(pattern) => installable.iter().copied().filter(|asset| matches_pattern(&asset.name, pattern)).collect(),
+        None if installable.len() == 1 => installable.clone(),
+        None => installable.iter().copied().filter(|asset| is_for_this_platform(&asset.name)).collect(),
+    };
+
+    match candidates.as_slice() {
+        [asset] => Ok(asset),
+        _ => {
+            let names: Vec<&str> = installable.iter().map(|asset| asset.name.as_str()).collect();
+            if names.is_empty() {
+                Err("Release has no assets".to_string())
+            } else {
+                Err(format!("Pick one asset with --asset:\n  {}", names.join("\n  ")))
+            }
+        }
+    }
+}
+
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return name == pattern;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || !name[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+fn is_for_this_platform(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let os_names: &[&str] = match std::env::consts::OS {
+        "macos" => &["darwin", "macos", "apple"],
+        "windows" => &["windows", "win64", "win32"],
+        os => &[os],
+    };
+    let arch_names: &[&str] = match std::env::consts::ARCH {
+        "x86_64" => &["x86_64", "amd64", "x64"],
+        "aarch64" => &["aarch64", "arm64"],
+        arch => &[arch],
+    };
+
+    os_names.iter().any(|os| name.contains(os)) && arch_names.iter().any(|arch| name.contains(arch))
+}
+
+/// Find the digest for `name` in `sha256sum` style output
+fn find_sum(text: &str, name: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let (sum, file) = line.split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        let valid = sum.len() == 64 && sum.chars().all(|c| c.is_ascii_hexdigit());
+        (valid && file == name).then(|| sum.to_lowercase())
+    })
+}
+
+fn fetch_text(client: &reqwest::blocking::Client, url: &str) -> Result<String, String> {
+    let response = client.get(url).send().map_err(|e| format!("Network error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response.text().map_err(|e| format!("Invalid response: {}", e))
+}
+
+/// Stream `url` into `dest` and return the SHA-256 of what was written
+pub(crate) fn download(client: &reqwest::blocking::Client, url: &str, dest: &Path) -> Result<String, String> {
+    let mut response = client.get(url).send().map_err(|e| format!("Download failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download failed: HTTP {}", response.status()));
+    }
+
+    let mut file = fs::File::create(dest).map_err(|e| format!("Can't create {}: {}", dest.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let read = response.read(&mut buffer).map_err(|e| format!("Download failed: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        file.write_all(&buffer[..read]).map_err(|e| format!("Can't write {}: {}", dest.display(), e))?;
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_sum, matches_pattern};
+
+    const SUM: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+    const OTHER_SUM: &str = "60303ae22b998861bce3b28f33eec1be758a213c86c93c076dbe9f558c11c752";
+
+    #[test]
+    fn patterns_without_wildcards_match_exactly() {
+        assert!(matches_pattern("tool.tar.gz", "tool.tar.gz"));
+        assert!(!matches_pattern("tool.tar.gz.sig", "tool.tar.gz"));
+        assert!(!matches_pattern("my-tool.tar.gz", "tool.tar.gz"));
+    }
+
+    #[test]
+    fn wildcards_match_any_run_of_characters() {
+        assert!(matches_pattern("tool-1.2.0-linux-x86_64.tar.gz", "tool-*-linux-*.tar.gz"));
+        assert!(matches_pattern("tool-linux.tar.gz", "*linux*"));
+        assert!(matches_pattern("anything", "*"));
+        assert!(matches_pattern("tool.tar.gz", "tool*.tar.gz"));
+        assert!(!matches_pattern("tool-1.2.0-darwin-arm64.tar.gz", "tool-*-linux-*.tar.gz"));
+        assert!(!matches_pattern("tool.zip", "*.tar.gz"));
+    }
+
+    #[test]
+    fn prefix_and_suffix_must_not_overlap() {
+        assert!(!matches_pattern("a", "a*a"));
+        assert!(matches_pattern("aa", "a*a"));
+        assert!(!matches_pattern("abc", "ab*bc"));
+    }
+
+    #[test]
+    fn middle_parts_match_in_order() {
+        assert!(matches_pattern("x-linux-amd64-y", "x*linux*amd64*y"));
+        assert!(!matches_pattern("x-amd64-linux-y", "x*linux*amd64*y"));
+    }
+
+    #[test]
+    fn sums_in_text_mode() {
+        let text = format!("{}  tool.tar.gz\n{}  tool.zip\n", SUM, OTHER_SUM);
+        assert_eq!(find_sum(&text, "tool.tar.gz"), Some(SUM.to_string()));
+        assert_eq!(find_sum(&text, "tool.zip"), Some(OTHER_SUM.to_string()));
+    }
+
+    #[test]
+    fn sums_in_binary_mode() {
+        let text = format!("{} *tool.tar.gz\n", SUM);
+        assert_eq!(find_sum(&text, "tool.tar.gz"), Some(SUM.to_string()));
+    }
+
+    #[test]
+    fn sums_are_lowercased() {
+        let text = format!("{}  tool.tar.gz\n", SUM.to_uppercase());
+        assert_eq!(find_sum(&text, "tool.tar.gz"), Some(SUM.to_string()));
+    }
+
+    #[test]
+    fn names_match_whole() {
+        let text = format!("{}  tool.tar.gz.sig\n{}  dist/tool.tar.gz\n", SUM, OTHER_SUM);
+        assert_eq!(find_sum(&text, "tool.tar.gz"), None);
+    }
+
+    #[test]
+    fn invalid_sums_are_skipped() {
+        let text = format!("{}  tool.tar.gz\nnot-a-sum  tool.tar.gz\n{}  tool.tar.gz\n", &SUM[1..], OTHER_SUM);
+        assert_eq!(find_sum(&text, "tool.tar.gz"), Some(OTHER_SUM.to_string()));
+        assert_eq!(find_sum("", "tool.tar.gz"), None);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::archive;
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::mirror;
+use crate::security;
+use crate::types::InstalledRepo;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn remove_repo(name: &str, forget_checksum: bool, yes: bool) {
+    let config = GitFetchConfig::load();
+
+    let repo = match config.resolve_repo(name) {
+        Ok(repo) => repo.clone(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let workspace_root = GitFetchConfig::workspace_root();
+    let mut doomed: Vec<PathBuf> = Vec::new();
+
+    if let Some(workspace) = &repo.workspace_path {
+        let workspace = PathBuf::from(workspace);
+        if workspace.exists() {
+            if !security::is_within_root(&workspace, &workspace_root) {
+                eprintln!("Refusing to delete workspace outside {}: {}",
+                    workspace_root.display(), workspace.display());
+                std::process::exit(1);
+            }
+            doomed.push(workspace);
+        }
+    }
+
+    let installed = PathBuf::from(&repo.path);
+    if installed.exists() && !doomed.iter().any(|dir| installed.starts_with(dir)) {
+        if !is_installed_copy(&installed, &repo, &workspace_root) {
+            eprintln!("Refusing to delete {}: not a gitfetch checkout of {}",
+                installed.display(), repo.url);
+            std::process::exit(1);
+        }
+        doomed.push(installed);
+    }
+
+    let versions = GitFetchConfig::versions_root().join(&repo.id);
+    if versions.exists() && security::is_within_root(&versions, &GitFetchConfig::versions_root()) {
+        doomed.push(versions);
+    }
+
+    let cache = mirror::mirror_path(&repo.url);
+    if cache.exists() && security::is_within_root(&cache, &GitFetchConfig::cache_root()) {
+        doomed.push(cache);
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("REMOVING: {}", repo.id);
+    println!("{}", "=".repeat(60));
+    println!("URL: {}", repo.url);
+
+    if doomed.is_empty() {
+        println!("Nothing left on disk, only the registry entry will be removed.");
+    } else {
+        println!("Will delete:");
+        for dir in &doomed {
+            println!("  {}", dir.display());
+        }
+    }
+
+    let has_checksum = config.get_checksum(&repo.url).is_some();
+    if forget_checksum && has_checksum {
+        println!("Will drop checksum registry entry for {}", repo.url);
+    }
+
+    if !yes && !security::prompt_user("\nProceed? (yes/no)") {
+        println!("Remove cancelled.");
+        std::process::exit(0);
+    }
+
+    for dir in &doomed {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            eprintln!("Failed to delete {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    let mut config = GitFetchConfig::load();
+    config.remove_repo(&repo.id);
+
+    if forget_checksum && has_checksum {
+        config.remove_checksum(&repo.url);
+    }
+
+    println!("\n✓ Removed {}", repo.id);
+
+    if !forget_checksum && has_checksum {
+        println!("💡 Checksum kept in registry, drop it with --forget-checksum");
+    }
+}
+
+/// Only delete an installed copy when it is clearly the checkout gitfetch made
+fn is_installed_copy(path: &Path, repo: &InstalledRepo, workspace_root: &Path) -> bool {
+    if security::is_within_root(path, workspace_root) {
+        return true;
+    }
+
+    let canonical = match path.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(_) => return false,
+    };
+
+    // Never touch $HOME or anything above it
+    if let Ok(home) = std::env::var("HOME") {
+        let home = Path::new(&home).canonicalize().unwrap_or_else(|_| PathBuf::from(&home));
+        if home.starts_with(&canonical) {
+            return false;
+        }
+    }
+
+    // Archive installs have a marker file standing in for .git
+    let is_checkout = if repo.archive {
+        archive::read_marker(&canonical).is_some()
+    } else {
+        canonical.join(".git").is_dir()
+    };
+    is_checkout
+        && git::get_remote_url(canonical.to_str().unwrap()).as_deref() == Some(repo.url.as_str())
+}
@@ -0,0 +1,162 @@
+use crate::checksum;
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::security;
+use crate::types::{InstalledRepo, RepoVersion};
+use super::clone::{copy_dir, replace_installed_copy};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Copy the currently installed checkout aside so it can be restored later.
+/// Failures only warn, losing a backup shouldn't block an install.
+/// A `restoring` version is kept even if the history is full.
+pub(crate) fn archive_installed(repo: &InstalledRepo, restoring: Option<&str>) {
+    let source = Path::new(&repo.path);
+    if !source.exists() {
+        return;
+    }
+
+    let commit_hash = match repo.commit_hash.clone().or_else(|| git::get_commit_hash(&repo.path)) {
+        Some(commit_hash) => commit_hash,
+        None => return,
+    };
+
+    let version_dir = GitFetchConfig::versions_root().join(&repo.id).join(&commit_hash);
+    if version_dir.exists() {
+        let _ = fs::remove_dir_all(&version_dir);
+    }
+    if let Some(parent) = version_dir.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+
+    if !copy_dir(source, &version_dir) {
+        eprintln!("⚠️  Couldn't save {:.8} for rollback", commit_hash);
+        return;
+    }
+
+    let total_hash = match checksum::calculate_repo_checksums(version_dir.to_str().unwrap()) {
+        Ok(checksum) => checksum.total_hash,
+        Err(e) => {
+            eprintln!("⚠️  Couldn't checksum saved version: {}", e);
+            let _ = fs::remove_dir_all(&version_dir);
+            return;
+        }
+    };
+
+    let version = RepoVersion {
+        commit_hash: commit_hash.clone(),
+        total_hash,
+        path: version_dir.to_string_lossy().to_string(),
+        saved_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    for pruned in GitFetchConfig::edit(|config| config.record_version(&repo.id, version, restoring)) {
+        let _ = fs::remove_dir_all(&pruned.path);
+    }
+
+    println!("Saved {:.8} for rollback", commit_hash);
+}
+
+pub fn rollback_repo(name: &str, to: Option<&str>, list: bool) {
+    let config = GitFetchConfig::load();
+
+    let repo = match config.resolve_repo(name) {
+        Ok(repo) => repo.clone(),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if repo.history.is_empty() {
+        eprintln!("No previous versions saved for {}", name);
+        std::process::exit(1);
+    }
+
+    if list {
+        print_history(&repo);
+        return;
+    }
+
+    let target = match to {
+        Some(prefix) => {
+            let matches: Vec<_> = repo.history.iter()
+                .filter(|version| version.commit_hash.starts_with(prefix))
+                .collect();
+            match matches.as_slice() {
+                [version] => (*version).clone(),
+                [] => {
+                    eprintln!("No saved version matches {}", prefix);
+                    print_history(&repo);
+                    std::process::exit(1);
+                }
+                _ => {
+                    eprintln!("{} is ambiguous, use more of the commit hash", prefix);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => repo.history.last().cloned().unwrap(),
+    };
+
+    println!("\n{}", "=".repeat(60));
+    println!("ROLLING BACK: {}", repo.id);
+    println!("{}", "=".repeat(60));
+    println!("Current: {:.8}", repo.commit_hash.as_deref().unwrap_or("unknown"));
+    println!("Target:  {:.8} (saved {})", target.commit_hash, target.saved_at);
+
+    // Refuse to restore a backup that changed since it was saved
+    let version_dir = PathBuf::from(&target.path);
+    let actual = checksum::calculate_repo_checksums(&target.path).unwrap_or_else(|e| {
+        eprintln!("Can't read saved version: {}", e);
+        std::process::exit(1);
+    });
+
+    if actual.total_hash != target.total_hash {
+        eprintln!("✗ Saved version {:.8} was modified since it was saved, refusing to restore", target.commit_hash);
+        std::process::exit(1);
+    }
+
+    // The current version becomes rollback-able in turn
+    archive_installed(&repo, Some(&target.commit_hash));
+
+    let installed_path = Path::new(&repo.path);
+    if let Err(e) = replace_installed_copy(&version_dir, installed_path) {
+        eprintln!("Restore failed: {}", e);
+        std::process::exit(1);
+    }
+    // Only now that it is installed again does the version leave the history
+    GitFetchConfig::edit(|config| config.take_version(&repo.id, &target.commit_hash));
+    let _ = fs::remove_dir_all(&version_dir);
+
+    // Keep the workspace in step so later updates start from the restored commit
+    if let Some(workspace) = &repo.workspace_path {
+        let repo_in_workspace = Path::new(workspace).join(&repo.name);
+        if repo_in_workspace.exists() && repo_in_workspace != installed_path {
+            if let Err(e) = security::run_sandboxed_git(
+                &repo_in_workspace,
+                &["-c", "advice.detachedHead=false", "checkout", "--force", &target.commit_hash],
+                false,
+            ) {
+                eprintln!("⚠️  Workspace checkout failed: {}", e);
+            }
+        }
+    }
+
+    let mut config = GitFetchConfig::load();
+    let verified = config.get_checksum(&repo.url)
+        .is_some_and(|registered| {
+            registered.commit_hash == target.commit_hash && registered.total_hash == target.total_hash
+        });
+    config.update_repo_commit(&repo.id, Some(target.commit_hash.clone()), verified);
+    config.set_scan_warnings(&repo.id, security::scan_for_suspicious_patterns(&repo.path, repo.submodules.is_some()));
+
+    println!("\n✓ {} rolled back to {:.8}", repo.id, target.commit_hash);
+}
+
+fn print_history(repo: &InstalledRepo) {
+    println!("\nSaved versions of {}:", repo.id);
+    for version in repo.history.iter().rev() {
+        println!("  {:.12}  {}", version.commit_hash, version.saved_at);
+    }
+}
@@ -1,53 +1,46 @@
-use crate::types::GitHubSearchResponse;
+use crate::auth;
+use crate::config::GitFetchConfig;
+use crate::forge;
+use crate::http;
 
 pub fn search_repos(query: &str) {
-    let search_query = if query.contains('/') {
-        format!("repo:{}", query)
+    let config = GitFetchConfig::load();
+    let (forge_name, query) = query.split_once(':').unwrap_or((forge::DEFAULT_FORGE, query));
+    let forge = forge::by_name(&config, forge_name).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let client = http::client();
+
+    // An exact owner/repo is looked up rather than searched for
+    let found = if query.contains('/') {
+        forge.metadata(&client, query).map(|repo| vec![repo])
     } else {
-        format!("{} in:name", query)
+        forge.search(&client, query)
     };
-    
-    let url = format!(
-        "https://api.github.com/search/repositories?q={}&sort=stars&order=desc",
-        urlencoding::encode(&search_query)
-    );
-
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("gitfetch/0.18")
-        .build()
-        .expect("Can't create HTTP client");
 
-    match client.get(&url).send() {
-        Ok(resp) => {
-            let status = resp.status();
-            
-            // Check for rate limiting (403 or 429)
-            if status.as_u16() == 403 || status.as_u16() == 429 {
-                eprintln!("\nSod off, you've been rate limited. Maybe use the GUI sometime?");
-                eprintln!("(GitHub allows 10 unauthenticated requests per minute)");
-                std::process::exit(1);
-            }
-            
-            if status.is_success() {
-                if let Ok(result) = resp.json::<GitHubSearchResponse>() {
-                    if result.items.is_empty() {
-                        println!("No repositories found.");
-                    } else {
-                        println!("\nFound {} repositories:\n", result.items.len());
-                        for repo in result.items.iter().take(10) {
-                            println!("  {}", repo.full_name);
-                            println!("    ⭐ {}", repo.stargazers_count);
-                            if let Some(desc) = &repo.description {
-                                println!("    {}", desc);
-                            }
-                            println!("    {}\n", repo.html_url);
-                        }
-                    }
+    match found {
+        Ok(repos) if repos.is_empty() => println!("No repositories found."),
+        Ok(repos) => {
+            println!("\nFound {} repositories:\n", repos.len());
+            for repo in repos.iter().take(10) {
+                println!("  {}", repo.full_name);
+                println!("    ⭐ {}", repo.stars);
+                if let Some(desc) = &repo.description {
+                    println!("    {}", desc);
                 }
-            } else {
-                eprintln!("GitHub API error: {}", status);
+                println!("    {}\n", repo.web_url);
+            }
+        }
+        Err(e) if e == http::NOT_FOUND => println!("No repositories found."),
+        Err(e) if e.starts_with(http::RATE_LIMITED) => {
+            eprintln!("\nSod off, you've been {}. Maybe use the GUI sometime?", e);
+            if forge_name == forge::DEFAULT_FORGE && auth::source(forge_name).is_none() {
+                eprintln!("(GitHub allows 10 unauthenticated requests per minute, gitfetch auth login raises that)");
             }
+            std::process::exit(1);
         }
-        Err(e) => eprintln!("Network error: {}", e),
+        Err(e) => eprintln!("{} API error: {}", forge_name, e),
     }
 }
@@ -0,0 +1,159 @@
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::security;
+use crate::types::{Manifest, ManifestRepo};
+use super::clone::{expand_repo_url, install_repo, repo_name_from_url, CloneOptions};
+use super::update::update_repo;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const TRUST_MODES: [&str; 3] = ["paranoid", "normal", "yolo"];
+
+pub fn sync_repos(manifest_path: &str, update: bool) {
+    security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
+
+    let contents = fs::read_to_string(manifest_path).unwrap_or_else(|e| {
+        eprintln!("Can't read {}: {}", manifest_path, e);
+        std::process::exit(1);
+    });
+
+    let manifest: Manifest = toml::from_str(&contents).unwrap_or_else(|e| {
+        eprintln!("Invalid manifest {}: {}", manifest_path, e);
+        std::process::exit(1);
+    });
+
+    let base_dir = Path::new(manifest_path)
+        .canonicalize()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| std::env::current_dir().expect("Can't get current directory"));
+
+    let mut declared_ids = Vec::new();
+    let mut installed = 0;
+    let mut updated = 0;
+    let mut failed = Vec::new();
+
+    for entry in &manifest.repos {
+        let result = sync_entry(&manifest, entry, &base_dir, update);
+        if let Ok(url) = expand_repo_url(&entry.repo) {
+            declared_ids.push(git::repo_id(&url));
+        }
+        match result {
+            Ok(SyncAction::Installed) => installed += 1,
+            Ok(SyncAction::Updated) => updated += 1,
+            Ok(SyncAction::Unchanged) => {}
+            Err(e) => {
+                eprintln!("✗ {}: {}", entry.repo, e);
+                failed.push(entry.repo.clone());
+            }
+        }
+    }
+
+    let config = GitFetchConfig::load();
+    let extra: Vec<_> = config.installed_repos.iter()
+        .filter(|repo| !declared_ids.contains(&repo.id))
+        .collect();
+
+    println!("\n{}", "=".repeat(60));
+    println!("SYNC SUMMARY");
+    println!("{}", "=".repeat(60));
+    println!("Declared:  {}", manifest.repos.len());
+    println!("Installed: {}", installed);
+    println!("Updated:   {}", updated);
+
+    if !extra.is_empty() {
+        println!("\nNot in {} ({}):", manifest_path, extra.len());
+        for repo in &extra {
+            println!("  {} ({})", repo.id, repo.url);
+        }
+        println!("💡 Remove with: gitfetch remove <name>");
+    }
+
+    if !failed.is_empty() {
+        println!("\n✗ {} failed: {}", failed.len(), failed.join(", "));
+        std::process::exit(1);
+    }
+}
+
+enum SyncAction {
+    Installed,
+    Updated,
+    Unchanged,
+}
+
+fn sync_entry(
+    manifest: &Manifest,
+    entry: &ManifestRepo,
+    base_dir: &Path,
+    update: bool,
+) -> Result<SyncAction, String> {
+    let repo_url = expand_repo_url(&entry.repo)?;
+
+    let trust_mode = entry.trust_mode.as_deref()
+        .or(manifest.defaults.trust_mode.as_deref())
+        .unwrap_or("normal");
+    if !TRUST_MODES.contains(&trust_mode) {
+        return Err(format!("Unknown trust mode: {}", trust_mode));
+    }
+
+    let install_path = match (&entry.path, &manifest.defaults.install_dir) {
+        (Some(path), _) => resolve_path(base_dir, path),
+        (None, Some(dir)) => resolve_path(base_dir, dir).join(repo_name_from_url(&repo_url)),
+        (None, None) => base_dir.join(repo_name_from_url(&repo_url)),
+    };
+
+    let config = GitFetchConfig::load();
+    let current = config.find_repo(&git::repo_id(&repo_url));
+
+    // Missing, re-pinned or moved repos go through the full clone pipeline again
+    let needs_install = match current {
+        None => true,
+        Some(repo) => {
+            repo.git_ref != entry.git_ref
+                || !Path::new(&repo.path).exists()
+                || Path::new(&repo.path) != install_path
+        }
+    };
+
+    if needs_install {
+        let options = CloneOptions {
+            trust_mode,
+            git_ref: entry.git_ref.as_deref(),
+            install_path: Some(install_path),
+            ..Default::default()
+        };
+        return match install_repo(&repo_url, &options)? {
+            Some(_) => Ok(SyncAction::Installed),
+            None => Err("Cancelled".to_string()),
+        };
+    }
+
+    let repo = current.unwrap();
+    if !update {
+        println!("✓ {} matches manifest", repo.id);
+        return Ok(SyncAction::Unchanged);
+    }
+
+    let before = repo.commit_hash.clone();
+    update_repo(repo, trust_mode)?;
+
+    let after = GitFetchConfig::load()
+        .find_repo(&repo.id)
+        .and_then(|repo| repo.commit_hash.clone());
+
+    if after != before {
+        Ok(SyncAction::Updated)
+    } else {
+        Ok(SyncAction::Unchanged)
+    }
+}
+
+/// Expand `~/` and resolve relative paths against the manifest's directory
+fn resolve_path(base_dir: &Path, path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/") {
+        let home = std::env::var("HOME").expect("No HOME directory?");
+        PathBuf::from(home).join(rest)
+    } else {
+        base_dir.join(path)
+    }
+}
@@ -0,0 +1,279 @@
+use crate::checksum;
+use crate::config::GitFetchConfig;
+use crate::git;
+use crate::mirror;
+use crate::security;
+use crate::submodules;
+use crate::types::InstalledRepo;
+use super::batch::{self, Outcome};
+use super::rollback::archive_installed;
+use super::clone::{
+    fetch_lfs_objects, install_repo, is_full_sha, prefetch_blobs, replace_installed_copy, resolve_requested_ref,
+    security_scan, CloneOptions,
+};
+use super::outdated::{ls_remote, pick_ref};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn update_repos(names: &[String], all: bool, jobs: usize, trust_mode: &str) {
+    security::check_bubblewrap().unwrap_or_else(|_| std::process::exit(1));
+
+    let targets = resolve_targets(names, all);
+
+    if targets.is_empty() {
+        println!("No repositories installed yet.");
+        return;
+    }
+
+    if let [repo] = targets.as_slice() {
+        if let Err(e) = update_repo(repo, trust_mode) {
+            eprintln!("✗ {}: {}", repo.id, e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let results = batch::run_parallel(&targets, jobs, |repo| {
+        let outcome = match update_repo(repo, trust_mode) {
+            Ok(()) => {
+                let after = GitFetchConfig::load()
+                    .find_repo(&repo.id)
+                    .and_then(|updated| updated.commit_hash.clone());
+                if after != repo.commit_hash {
+                    Outcome::Done(format!(
+                        "{:.8} -> {:.8}",
+                        repo.commit_hash.as_deref().unwrap_or("unknown"),
+                        after.as_deref().unwrap_or("unknown")
+                    ))
+                } else {
+                    Outcome::Skipped(format!("unchanged at {:.8}", repo.commit_hash.as_deref().unwrap_or("unknown")))
+                }
+            }
+            Err(e) => Outcome::Failed(e),
+        };
+        (repo.id.clone(), outcome)
+    });
+
+    if batch::print_summary("UPDATE SUMMARY", &results) > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Installed repos named on the command line, or all of them with `--all`.
+/// Exits on names that don't resolve.
+pub(crate) fn resolve_targets(names: &[String], all: bool) -> Vec<InstalledRepo> {
+    let config = GitFetchConfig::load();
+
+    if all {
+        return config.installed_repos.clone();
+    }
+    if names.is_empty() {
+        eprintln!("Specify a repository name or --all");
+        std::process::exit(1);
+    }
+
+    let mut targets: Vec<InstalledRepo> = Vec::new();
+    for name in names {
+        match config.resolve_repo(name) {
+            Ok(repo) if targets.iter().any(|target| target.id == repo.id) => {}
+            Ok(repo) => targets.push(repo.clone()),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+    targets
+}
+
+pub(crate) fn update_repo(repo: &InstalledRepo, trust_mode: &str) -> Result<(), String> {
+    if repo.archive {
+        return update_archive(repo, trust_mode);
+    }
+
+    let workspace = repo.workspace_path.as_ref()
+        .map(PathBuf::from)
+        .ok_or("No workspace recorded, clone it again")?;
+    let repo_in_workspace = workspace.join(&repo.name);
+
+    if !repo_in_workspace.join(".git").exists() {
+        return Err(format!("Workspace missing: {}", repo_in_workspace.display()));
+    }
+
+    let workspace_str = repo_in_workspace.to_str().unwrap();
+
+    println!("\n{}", "=".repeat(60));
+    println!("UPDATING: {}", repo.url);
+    println!("{}", "=".repeat(60));
+    println!("Trust mode: {}", trust_mode);
+    if let Some(git_ref) = &repo.git_ref {
+        println!("Ref:        {}", git_ref);
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 1: FETCH (with network)");
+    println!("{}", "=".repeat(60));
+
+    // With a cached mirror only the mirror talks to the remote, the workspace fetches from it offline
+    let mirror = mirror::existing(&repo.url)
+        .filter(|_| mirror::applies(&repo.url, repo.depth, repo.filter.as_deref()))
+        .and_then(|_| {
+            println!("Updating object cache...");
+            mirror::refresh(&repo.url)
+                .map_err(|e| println!("⚠️  Object cache unavailable ({}), fetching without it", e))
+                .ok()
+        });
+
+    if let Some(mirror) = &mirror {
+        security::run_sandboxed_git_with_mirror(
+            &repo_in_workspace,
+            mirror,
+            &["fetch", "--prune", security::MIRROR_MOUNT, "+refs/heads/*:refs/remotes/origin/*", "+refs/tags/*:refs/tags/*"],
+            false,
+        )
+        .map_err(|e| format!("Fetch from cache failed: {}", e))?;
+    } else {
+        // Keep shallow clones shallow, a plain fetch would pull in all history behind new tags.
+        // --force lets tags that were moved upstream follow, pinned re-tagged refs depend on it.
+        let depth_arg = repo.depth.map(|depth| format!("--depth={}", depth));
+        let mut fetch_args = vec!["fetch", "--prune", "--tags", "--force"];
+        fetch_args.extend(depth_arg.as_deref());
+        fetch_args.push("origin");
+
+        security::run_sandboxed_fetch(&repo_in_workspace, &repo.url, &fetch_args)
+            .map_err(|e| format!("Fetch failed: {}", e))?;
+    }
+
+    // Pinned repos follow their recorded ref, everything else follows the default branch
+    let new_commit = match &repo.git_ref {
+        Some(git_ref) => resolve_requested_ref(&repo_in_workspace, git_ref)
+            .ok_or_else(|| format!("Pinned ref {} no longer exists upstream", git_ref))?,
+        None => resolve_exact_ref(&repo_in_workspace, "refs/remotes/origin/HEAD")
+            .or_else(|| resolve_exact_ref(&repo_in_workspace, "FETCH_HEAD"))
+            .ok_or("Can't resolve fetched commit")?,
+    };
+    let old_commit = repo.commit_hash.clone()
+        .or_else(|| git::get_commit_hash(workspace_str));
+
+    if old_commit.as_deref() == Some(new_commit.as_str()) {
+        println!("✓ Already up to date ({:.8})", new_commit);
+        return Ok(());
+    }
+
+    println!("Commit: {:.8} -> {:.8}", old_commit.as_deref().unwrap_or("unknown"), new_commit);
+
+    prefetch_blobs(&repo_in_workspace, &new_commit, &repo.sparse_paths)?;
+
+    let policy = repo.submodules.as_deref().unwrap_or("none");
+    let fetched_submodules = submodules::fetch_submodules(&repo_in_workspace, &repo.url, &new_commit, policy, trust_mode)?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("STAGE 2: CHECKOUT (network isolated, hooks disabled)");
+    println!("{}", "=".repeat(60));
+
+    // Save the current version first, the checkout may overwrite it in place
+    archive_installed(repo, None);
+
+    security::run_sandboxed_git(&repo_in_workspace, &["-c", "advice.detachedHead=false", "checkout", "--force", &new_commit], false)
+        .map_err(|e| format!("Checkout failed: {}", e))?;
+    submodules::checkout_submodules(&repo_in_workspace, &fetched_submodules)?;
+    fetch_lfs_objects(&repo_in_workspace, &repo.url, repo.lfs_max_size)?;
+
+    // The registry only vouches for the commit it was recorded at
+    let config = GitFetchConfig::load();
+    let mut verified = false;
+    if let Some(expected_checksum) = config.get_checksum(&repo.url) {
+        if expected_checksum.commit_hash == new_commit {
+            println!("\n{}", "=".repeat(60));
+            println!("VERIFYING CHECKSUMS");
+            println!("{}", "=".repeat(60));
+
+            match checksum::verify_repo_checksums(workspace_str, expected_checksum) {
+                Ok(true) => verified = true,
+                Ok(false) => {}
+                Err(e) => eprintln!("Verification error: {}", e),
+            }
+        } else {
+            println!("\nRegistry checksum is for commit {:.8}, new commit is unverified.",
+                expected_checksum.commit_hash);
+        }
+    }
+
+    let warnings = security_scan(&repo_in_workspace, policy != "none");
+
+    // Paranoid mode: always prompt
+    // Normal mode: prompt unless checksum verified
+    // YOLO mode: never prompt
+    let accepted = match trust_mode {
+        "yolo" => true,
+        "paranoid" => security::prompt_user(&format!("\nAccept commit {:.8} of {}? (yes/no)", new_commit, repo.id)),
+        _ => verified || security::prompt_user(&format!("\nAccept unverified commit {:.8} of {}? (yes/no)", new_commit, repo.id)),
+    };
+
+    if !accepted {
+        // Submodule worktrees stay at the new commits, the next update checks them out again
+        if let Some(old_commit) = &old_commit {
+            security::run_sandboxed_git(&repo_in_workspace, &["-c", "advice.detachedHead=false", "checkout", "--force", old_commit], false)
+                .map_err(|e| format!("Failed to restore previous commit: {}", e))?;
+        }
+        if let Some(version) = old_commit.as_deref().and_then(|commit| {
+            GitFetchConfig::edit(|config| config.take_version(&repo.id, commit))
+        }) {
+            let _ = fs::remove_dir_all(&version.path);
+        }
+        println!("Update declined, keeping {:.8}", old_commit.as_deref().unwrap_or("unknown"));
+        return Ok(());
+    }
+
+    let installed_path = Path::new(&repo.path);
+    if installed_path != repo_in_workspace {
+        replace_installed_copy(&repo_in_workspace, installed_path)?;
+    }
+
+    GitFetchConfig::edit(|config| {
+        config.update_repo_commit(&repo.id, Some(new_commit.clone()), verified);
+        config.set_scan_warnings(&repo.id, warnings);
+    });
+
+    println!("\n✓ {} updated to {:.8}", repo.id, new_commit);
+
+    if !verified {
+        println!("💡 Create checksum: gitfetch checksum {} --save", repo.path);
+    }
+
+    Ok(())
+}
+
+/// Commit a fully spelled out ref points at, without the tag/branch guessing of `resolve_requested_ref`
+fn resolve_exact_ref(repo_path: &Path, full_ref: &str) -> Option<String> {
+    let spec = format!("{}^{{commit}}", full_ref);
+    security::sandboxed_git_output(repo_path, &["rev-parse", "--verify", "--quiet", &spec], false)
+        .ok()
+        .filter(|commit| !commit.is_empty())
+}
+
+/// Snapshots have nothing to fetch into, download the new commit's tarball instead
+fn update_archive(repo: &InstalledRepo, trust_mode: &str) -> Result<(), String> {
+    if repo.git_ref.as_deref().is_some_and(is_full_sha) {
+        println!("✓ {} is pinned to {:.8}", repo.id, repo.commit_hash.as_deref().unwrap_or("unknown"));
+        return Ok(());
+    }
+
+    let refs = ls_remote(&repo.url)?;
+    let (_, new_commit) = pick_ref(&refs, repo.git_ref.as_deref())
+        .ok_or_else(|| format!("{} not found on remote", repo.git_ref.as_deref().unwrap_or("HEAD")))?;
+    if repo.commit_hash.as_deref() == Some(new_commit.as_str()) {
+        println!("✓ Already up to date ({:.8})", new_commit);
+        return Ok(());
+    }
+
+    let options = CloneOptions {
+        trust_mode,
+        git_ref: repo.git_ref.as_deref(),
+        commit: Some(&new_commit),
+        install_path: Some(PathBuf::from(&repo.path)),
+        archive: true,
+        ..Default::default()
+    };
+    install_repo(&repo.url, &options).map(|_| ())
+}
@@ -1,17 +1,57 @@
-use crate::types::{InstalledRepo, RepoChecksum};
+use crate::git;
+use crate::types::{ForgeKind, ForgeProfile, InstalledRelease, InstalledRepo, RepoChecksum, RepoVersion};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Held while a parallel job loads, changes and saves the config
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+/// Numbers the temp files of `save`, so parallel saves never share one
+static SAVE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GitFetchConfig {
     pub installed_repos: Vec<InstalledRepo>,
     pub checksum_registry: HashMap<String, RepoChecksum>,
+    /// How many previous versions of each repo to keep for rollback
+    #[serde(default = "default_keep_versions")]
+    pub keep_versions: usize,
+    /// Hosts repos and submodules may be fetched from, empty allows any host
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Legacy GitHub API base URL, moved to the github forge profile on load
+    #[serde(default, skip_serializing)]
+    pub github_api_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub installed_releases: Vec<InstalledRelease>,
+    /// Known SHA-256 digests of release assets, keyed by `<id>@<tag>/<asset>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub release_registry: HashMap<String, String>,
+    /// Extra forges (self-hosted GitLab, Gitea, ...) by shorthand prefix, can override built-in ones
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub forges: HashMap<String, ForgeProfile>,
+}
+
+fn default_keep_versions() -> usize {
+    3
 }
 
 impl GitFetchConfig {
     pub fn load() -> Self {
+        let config = Self::read();
+        if !config.needs_migration() {
+            return config;
+        }
+        // Migrating moves directories and saves, so it waits for the lock like any other change
+        let _guard = CONFIG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Self::read_and_migrate()
+    }
+
+    /// The config as on disk
+    fn read() -> Self {
         let config_path = Self::config_path();
         if config_path.exists() {
             let contents = fs::read_to_string(&config_path)
@@ -19,13 +59,125 @@ impl GitFetchConfig {
             serde_json::from_str(&contents).unwrap_or_else(|_| GitFetchConfig {
                 installed_repos: vec![],
                 checksum_registry: HashMap::new(),
+                keep_versions: default_keep_versions(),
+                allowed_hosts: vec![],
+                github_api_url: None,
+                installed_releases: vec![],
+                release_registry: HashMap::new(),
+                forges: HashMap::new(),
             })
         } else {
             GitFetchConfig {
                 installed_repos: vec![],
                 checksum_registry: HashMap::new(),
+                keep_versions: default_keep_versions(),
+                allowed_hosts: vec![],
+                github_api_url: None,
+                installed_releases: vec![],
+                release_registry: HashMap::new(),
+                forges: HashMap::new(),
+            }
+        }
+    }
+
+    /// Turn the old top-level `github_api_url` into the `api_url` of the github forge profile
+    fn migrate_github_api_url(&mut self) -> bool {
+        let Some(api_url) = self.github_api_url.take() else {
+            return false;
+        };
+
+        let profile = self.forges.entry("github".to_string()).or_insert_with(|| ForgeProfile {
+            kind: ForgeKind::Github,
+            web_url: "https://github.com".to_string(),
+            api_url: None,
+            clone_host: None,
+        });
+        profile.api_url.get_or_insert(api_url);
+        true
+    }
+
+    /// Upgrade entries from before repos were keyed by `host/owner/name`:
+    /// assign ids, drop duplicate records and move legacy `workspace/<name>`
+    /// and `versions/<name>` directories under their qualified paths.
+    fn migrate(&mut self) -> bool {
+        if self.installed_repos.iter().all(|repo| !repo.id.is_empty()) {
+            return false;
+        }
+
+        for repo in self.installed_repos.iter_mut().filter(|repo| repo.id.is_empty()) {
+            repo.id = git::repo_id(&repo.url);
+        }
+
+        // Old versions appended a record per clone, the newest one wins
+        let mut seen = Vec::new();
+        let mut repos: Vec<InstalledRepo> = self.installed_repos.drain(..).rev()
+            .filter(|repo| {
+                let fresh = !seen.contains(&repo.id);
+                seen.push(repo.id.clone());
+                fresh
+            })
+            .collect();
+        repos.reverse();
+
+        let workspace_root = Self::workspace_root();
+        let versions_root = Self::versions_root();
+
+        for repo in &mut repos {
+            if let Some(old_workspace) = repo.workspace_path.clone().map(PathBuf::from) {
+                let new_workspace = workspace_root.join(&repo.id);
+                if old_workspace.parent() == Some(workspace_root.as_path()) && old_workspace != new_workspace {
+                    let checkout = old_workspace.join(&repo.name);
+                    let owned = git::get_remote_url(&checkout.to_string_lossy()).as_deref() == Some(repo.url.as_str());
+                    if owned && !new_workspace.exists() && move_dir(&old_workspace, &new_workspace) {
+                        if let Ok(rest) = Path::new(&repo.path).strip_prefix(&old_workspace) {
+                            repo.path = new_workspace.join(rest).to_string_lossy().to_string();
+                        }
+                        repo.workspace_path = Some(new_workspace.to_string_lossy().to_string());
+                    } else if !owned {
+                        // The legacy workspace was overwritten by a same-named repo
+                        repo.workspace_path = None;
+                    }
+                }
+            }
+
+            let old_versions = versions_root.join(&repo.name);
+            let new_versions = versions_root.join(&repo.id);
+            if !repo.history.is_empty() && old_versions != new_versions
+                && old_versions.exists() && !new_versions.exists()
+                && move_dir(&old_versions, &new_versions)
+            {
+                for version in &mut repo.history {
+                    if let Ok(rest) = Path::new(&version.path).strip_prefix(&old_versions) {
+                        version.path = new_versions.join(rest).to_string_lossy().to_string();
+                    }
+                }
             }
         }
+
+        self.installed_repos = repos;
+        true
+    }
+
+    /// Load, change and save the config while holding the lock, so parallel
+    /// jobs can't overwrite each other's records with a stale copy
+    pub fn edit<T>(change: impl FnOnce(&mut GitFetchConfig) -> T) -> T {
+        let _guard = CONFIG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut config = Self::read_and_migrate();
+        change(&mut config)
+    }
+
+    /// Read the config and save it if it was upgraded, only with `CONFIG_LOCK` held
+    fn read_and_migrate() -> Self {
+        let mut config = Self::read();
+        let migrated = config.migrate();
+        if config.migrate_github_api_url() || migrated {
+            config.save();
+        }
+        config
+    }
+
+    fn needs_migration(&self) -> bool {
+        self.github_api_url.is_some() || self.installed_repos.iter().any(|repo| repo.id.is_empty())
     }
 
     pub fn save(&self) {
@@ -35,7 +187,15 @@ impl GitFetchConfig {
         }
         let contents = serde_json::to_string_pretty(self)
             .expect("Failed to serialize config");
-        fs::write(&config_path, contents).expect("Can't write config file");
+
+        // Write and rename so a concurrent load never sees a half-written file
+        let temp_path = config_path.with_extension(format!(
+            "json.{}.{}.tmp",
+            std::process::id(),
+            SAVE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp_path, contents).expect("Can't write config file");
+        fs::rename(&temp_path, &config_path).expect("Can't write config file");
     }
 
     pub fn config_path() -> PathBuf {
@@ -43,32 +203,173 @@ impl GitFetchConfig {
         PathBuf::from(home).join(".config").join("gitfetch").join("config.json")
     }
 
-    pub fn add_repo(
-        &mut self,
-        name: String,
-        url: String,
-        path: String,
-        commit_hash: Option<String>,
-        verified: bool,
-        workspace_path: Option<String>,
-    ) {
-        self.installed_repos.push(InstalledRepo {
-            name,
-            url,
-            path,
-            commit_hash,
-            verified,
-            workspace_path,
+    /// API tokens saved by `gitfetch auth login`, kept apart from the config so it can stay 0600
+    pub fn tokens_path() -> PathBuf {
+        Self::config_path().with_file_name("tokens.json")
+    }
+
+    pub fn workspace_root() -> PathBuf {
+        let home = std::env::var("HOME").expect("No HOME directory?");
+        PathBuf::from(home).join(".gitfetch").join("workspace")
+    }
+
+    pub fn versions_root() -> PathBuf {
+        let home = std::env::var("HOME").expect("No HOME directory?");
+        PathBuf::from(home).join(".gitfetch").join("versions")
+    }
+
+    pub fn cache_root() -> PathBuf {
+        let home = std::env::var("HOME").expect("No HOME directory?");
+        PathBuf::from(home).join(".gitfetch").join("cache")
+    }
+
+    /// Forge API responses kept for conditional requests
+    pub fn http_cache_root() -> PathBuf {
+        let home = std::env::var("HOME").expect("No HOME directory?");
+        PathBuf::from(home).join(".gitfetch").join("http")
+    }
+
+    pub fn add_repo(&mut self, mut repo: InstalledRepo) {
+        // Re-cloning replaces the old record instead of stacking a stale one,
+        // but the rollback history carries over
+        if let Some(existing) = self.find_repo(&repo.id) {
+            if repo.history.is_empty() {
+                repo.history = existing.history.clone();
+            }
+        }
+        self.installed_repos.retain(|existing| existing.id != repo.id);
+        self.installed_repos.push(repo);
+        self.save();
+    }
+
+    pub fn find_repo(&self, id: &str) -> Option<&InstalledRepo> {
+        self.installed_repos.iter().find(|repo| repo.id == id)
+    }
+
+    /// Look up a repo by `host/owner/name`, `owner/name` or bare name,
+    /// refusing short forms that match more than one install
+    pub fn resolve_repo(&self, query: &str) -> Result<&InstalledRepo, String> {
+        if let Some(repo) = self.find_repo(query) {
+            return Ok(repo);
+        }
+
+        let suffix = format!("/{}", query);
+        let matches: Vec<&InstalledRepo> = self.installed_repos.iter()
+            .filter(|repo| repo.name == query || repo.id.ends_with(&suffix))
+            .collect();
+
+        match matches.as_slice() {
+            [repo] => Ok(repo),
+            [] => Err(format!("Repository not installed: {}", query)),
+            _ => {
+                let ids: Vec<&str> = matches.iter().map(|repo| repo.id.as_str()).collect();
+                Err(format!("{} is ambiguous, use one of: {}", query, ids.join(", ")))
+            }
+        }
+    }
+
+    pub fn update_repo_commit(&mut self, id: &str, commit_hash: Option<String>, verified: bool) {
+        for repo in self.installed_repos.iter_mut().filter(|repo| repo.id == id) {
+            repo.commit_hash = commit_hash.clone();
+            repo.verified = verified;
+        }
+        self.save();
+    }
+
+    pub fn set_workspace_path(&mut self, id: &str, workspace_path: Option<String>) {
+        for repo in self.installed_repos.iter_mut().filter(|repo| repo.id == id) {
+            repo.workspace_path = workspace_path.clone();
+        }
+        self.save();
+    }
+
+    pub fn set_scan_warnings(&mut self, id: &str, warnings: Vec<String>) {
+        for repo in self.installed_repos.iter_mut().filter(|repo| repo.id == id) {
+            repo.scan_warnings = Some(warnings.clone());
+        }
+        self.save();
+    }
+
+    /// Append a saved version and return the ones that fell off the end
+    /// Add `version` to the history and return the oldest versions over `keep_versions`.
+    /// `restoring` is about to leave the history by rollback, so it is neither pruned nor counted.
+    pub fn record_version(&mut self, id: &str, version: RepoVersion, restoring: Option<&str>) -> Vec<RepoVersion> {
+        let mut pruned = Vec::new();
+        let keep = self.keep_versions;
+        if let Some(repo) = self.installed_repos.iter_mut().find(|repo| repo.id == id) {
+            repo.history.retain(|old| old.commit_hash != version.commit_hash);
+            repo.history.push(version);
+            let is_restoring = |old: &RepoVersion| Some(old.commit_hash.as_str()) == restoring;
+            let counted = repo.history.iter().filter(|old| !is_restoring(old)).count();
+            let mut excess = counted.saturating_sub(keep);
+            repo.history.retain(|old| {
+                if excess == 0 || is_restoring(old) {
+                    return true;
+                }
+                excess -= 1;
+                pruned.push(old.clone());
+                false
+            });
+        }
+        self.save();
+        pruned
+    }
+
+    pub fn take_version(&mut self, id: &str, commit_hash: &str) -> Option<RepoVersion> {
+        let repo = self.installed_repos.iter_mut().find(|repo| repo.id == id)?;
+        let index = repo.history.iter().position(|version| version.commit_hash == commit_hash)?;
+        let version = repo.history.remove(index);
+        self.save();
+        Some(version)
+    }
+
+    pub fn remove_repo(&mut self, id: &str) -> Vec<InstalledRepo> {
+        let (removed, kept) = self.installed_repos
+            .drain(..)
+            .partition(|repo| repo.id == id);
+        self.installed_repos = kept;
+        self.save();
+        removed
+    }
+
+    pub fn add_release(&mut self, release: InstalledRelease) {
+        self.installed_releases.retain(|existing| {
+            existing.id != release.id || existing.path != release.path
         });
+        self.installed_releases.push(release);
         self.save();
     }
 
+    pub fn add_release_digest(&mut self, key: String, sha256: String) {
+        self.release_registry.insert(key, sha256);
+        self.save();
+    }
+
+    pub fn is_host_allowed(&self, url: &str) -> bool {
+        self.allowed_hosts.is_empty() || self.allowed_hosts.contains(&git::repo_host(url))
+    }
+
     pub fn add_checksum(&mut self, repo_url: String, checksum: RepoChecksum) {
         self.checksum_registry.insert(repo_url, checksum);
         self.save();
     }
 
+    pub fn remove_checksum(&mut self, repo_url: &str) -> Option<RepoChecksum> {
+        let removed = self.checksum_registry.remove(repo_url);
+        self.save();
+        removed
+    }
+
     pub fn get_checksum(&self, repo_url: &str) -> Option<&RepoChecksum> {
         self.checksum_registry.get(repo_url)
     }
 }
+
+fn move_dir(from: &Path, to: &Path) -> bool {
+    if let Some(parent) = to.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return false;
+        }
+    }
+    fs::rename(from, to).is_ok()
+}
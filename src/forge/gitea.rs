@@ -0,0 +1,51 @@
+use super::{Api, Forge, RepoInfo};
+use crate::types::{GiteaRepo, GiteaSearchResponse};
+use reqwest::blocking::Client;
+
+/// Gitea and Forgejo instances such as Codeberg
+pub struct Gitea {
+    web: String,
+    api: Api,
+}
+
+impl Gitea {
+    pub fn new(web: String, api: Api) -> Self {
+        Gitea { web, api }
+    }
+}
+
+impl From<GiteaRepo> for RepoInfo {
+    fn from(repo: GiteaRepo) -> Self {
+        RepoInfo {
+            full_name: repo.full_name,
+            description: repo.description.filter(|description| !description.is_empty()),
+            stars: repo.stars_count,
+            web_url: repo.html_url,
+        }
+    }
+}
+
+impl Forge for Gitea {
+    fn repo_url(&self, path: &str) -> String {
+        format!("{}/{}", self.web, path)
+    }
+
+    fn search(&self, client: &Client, query: &str) -> Result<Vec<RepoInfo>, String> {
+        let url = format!(
+            "{}/repos/search?q={}&sort=stars&order=desc&limit=10",
+            self.api.base,
+            urlencoding::encode(query)
+        );
+        let response: GiteaSearchResponse = self.api.fetch_json(client, &url)?;
+        Ok(response.data.into_iter().map(RepoInfo::from).collect())
+    }
+
+    fn metadata(&self, client: &Client, path: &str) -> Result<RepoInfo, String> {
+        let repo: GiteaRepo = self.api.fetch_json(client, &format!("{}/repos/{}", self.api.base, path))?;
+        Ok(repo.into())
+    }
+
+    fn archive_url(&self, path: &str, commit: &str) -> String {
+        format!("{}/repos/{}/archive/{}.tar.gz", self.api.base, path, commit)
+    }
+}
@@ -0,0 +1,51 @@
+use super::{Api, Forge, RepoInfo};
+use crate::types::{GitHubRepo, GitHubSearchResponse};
+use reqwest::blocking::Client;
+
+/// github.com or a GitHub Enterprise server
+pub struct GitHub {
+    web: String,
+    api: Api,
+}
+
+impl GitHub {
+    pub fn new(web: String, api: Api) -> Self {
+        GitHub { web, api }
+    }
+}
+
+impl From<GitHubRepo> for RepoInfo {
+    fn from(repo: GitHubRepo) -> Self {
+        RepoInfo {
+            full_name: repo.full_name,
+            description: repo.description,
+            stars: repo.stargazers_count,
+            web_url: repo.html_url,
+        }
+    }
+}
+
+impl Forge for GitHub {
+    fn repo_url(&self, path: &str) -> String {
+        format!("{}/{}", self.web, path)
+    }
+
+    fn search(&self, client: &Client, query: &str) -> Result<Vec<RepoInfo>, String> {
+        let url = format!(
+            "{}/search/repositories?q={}&sort=stars&order=desc",
+            self.api.base,
+            urlencoding::encode(&format!("{} in:name", query))
+        );
+        let response: GitHubSearchResponse = self.api.fetch_json(client, &url)?;
+        Ok(response.items.into_iter().map(RepoInfo::from).collect())
+    }
+
+    fn metadata(&self, client: &Client, path: &str) -> Result<RepoInfo, String> {
+        let repo: GitHubRepo = self.api.fetch_json(client, &format!("{}/repos/{}", self.api.base, path))?;
+        Ok(repo.into())
+    }
+
+    fn archive_url(&self, path: &str, commit: &str) -> String {
+        format!("{}/repos/{}/tarball/{}", self.api.base, path, commit)
+    }
+}
@@ -0,0 +1,57 @@
+use super::{Api, Forge, RepoInfo};
+use crate::types::GitLabProject;
+use reqwest::blocking::Client;
+
+/// gitlab.com or a self-hosted GitLab, projects may sit in nested groups
+pub struct GitLab {
+    web: String,
+    api: Api,
+}
+
+impl GitLab {
+    pub fn new(web: String, api: Api) -> Self {
+        GitLab { web, api }
+    }
+}
+
+impl From<GitLabProject> for RepoInfo {
+    fn from(project: GitLabProject) -> Self {
+        RepoInfo {
+            full_name: project.path_with_namespace,
+            description: project.description.filter(|description| !description.is_empty()),
+            stars: project.star_count,
+            web_url: project.web_url,
+        }
+    }
+}
+
+impl Forge for GitLab {
+    fn repo_url(&self, path: &str) -> String {
+        format!("{}/{}", self.web, path)
+    }
+
+    fn search(&self, client: &Client, query: &str) -> Result<Vec<RepoInfo>, String> {
+        let url = format!(
+            "{}/projects?search={}&order_by=star_count&sort=desc&per_page=10",
+            self.api.base,
+            urlencoding::encode(query)
+        );
+        let projects: Vec<GitLabProject> = self.api.fetch_json(client, &url)?;
+        Ok(projects.into_iter().map(RepoInfo::from).collect())
+    }
+
+    fn metadata(&self, client: &Client, path: &str) -> Result<RepoInfo, String> {
+        // Projects are addressed by their URL-encoded full path
+        let project: GitLabProject = self.api.fetch_json(client, &format!("{}/projects/{}", self.api.base, urlencoding::encode(path)))?;
+        Ok(project.into())
+    }
+
+    fn archive_url(&self, path: &str, commit: &str) -> String {
+        format!(
+            "{}/projects/{}/repository/archive.tar.gz?sha={}",
+            self.api.base,
+            urlencoding::encode(path),
+            commit
+        )
+    }
+}
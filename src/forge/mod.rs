@@ -0,0 +1,205 @@
+mod gitea;
+mod github;
+mod gitlab;
+mod sourcehut;
+
+use crate::auth;
+use crate::config::GitFetchConfig;
+use crate::git::{self, Transport};
+use crate::http;
+use crate::types::{ForgeKind, ForgeProfile};
+use reqwest::blocking::Client;
+use std::cell::OnceCell;
+
+/// Forge used for shorthands without a `<forge>:` prefix
+pub const DEFAULT_FORGE: &str = "github";
+
+/// What search results and metadata lookups show about a repository
+pub struct RepoInfo {
+    pub full_name: String,
+    pub description: Option<String>,
+    pub stars: u32,
+    pub web_url: String,
+}
+
+/// A code hosting service: how its repositories are addressed and how to query its API
+pub trait Forge {
+    /// Clone URL of the repository at `path` (`owner/repo`, `group/subgroup/project`, ...)
+    fn repo_url(&self, path: &str) -> String;
+    /// Repositories matching `query`, most starred first
+    fn search(&self, client: &Client, query: &str) -> Result<Vec<RepoInfo>, String>;
+    /// Details of the repository at `path`
+    fn metadata(&self, client: &Client, path: &str) -> Result<RepoInfo, String>;
+    /// Download URL of a `.tar.gz` snapshot of `commit`
+    fn archive_url(&self, path: &str, commit: &str) -> String;
+}
+
+fn builtin_profiles() -> Vec<(String, ForgeProfile)> {
+    [
+        ("github", ForgeKind::Github, "https://github.com"),
+        ("gitlab", ForgeKind::Gitlab, "https://gitlab.com"),
+        ("codeberg", ForgeKind::Gitea, "https://codeberg.org"),
+        ("sourcehut", ForgeKind::Sourcehut, "https://git.sr.ht"),
+    ]
+    .into_iter()
+    .map(|(name, kind, url)| {
+        let profile = ForgeProfile { kind, web_url: url.to_string(), api_url: None, clone_host: None };
+        (name.to_string(), profile)
+    })
+    .collect()
+}
+
+/// Built-in forges plus the ones from the config, which win on equal names
+pub fn profiles(config: &GitFetchConfig) -> Vec<(String, ForgeProfile)> {
+    let mut profiles = builtin_profiles();
+    for (name, profile) in &config.forges {
+        profiles.retain(|(existing, _)| existing != name);
+        profiles.push((name.clone(), profile.clone()));
+    }
+    profiles.sort_by(|(a, _), (b, _)| a.cmp(b));
+    profiles
+}
+
+/// API base of a profile: `api_url` if set, else where the service usually puts it
+fn api_base(profile: &ForgeProfile) -> String {
+    if let Some(api_url) = &profile.api_url {
+        return api_url.trim_end_matches('/').to_string();
+    }
+
+    let web = profile.web_url.trim_end_matches('/');
+    match profile.kind {
+        // github.com has its API on a separate host, Enterprise servers under /api/v3
+        ForgeKind::Github if web == "https://github.com" => "https://api.github.com".to_string(),
+        ForgeKind::Github => format!("{}/api/v3", web),
+        ForgeKind::Gitlab => format!("{}/api/v4", web),
+        ForgeKind::Gitea => format!("{}/api/v1", web),
+        // Nothing anonymous to call, see sourcehut.rs
+        ForgeKind::Sourcehut => web.to_string(),
+    }
+}
+
+/// Base of clone URLs: the web URL, moved to `clone_host` if one is set
+fn clone_base(profile: &ForgeProfile) -> String {
+    let web = profile.web_url.trim_end_matches('/');
+    match (&profile.clone_host, web.split_once("://")) {
+        (Some(host), Some((scheme, rest))) => {
+            let prefix = rest.find('/').map(|index| &rest[index..]).unwrap_or("");
+            format!("{}://{}{}", scheme, host.trim_end_matches('/'), prefix)
+        }
+        _ => web.to_string(),
+    }
+}
+
+/// A forge's API base URL and the token that may be sent to it, looked up on first use
+pub struct Api {
+    pub base: String,
+    forge: String,
+    token: OnceCell<Option<String>>,
+}
+
+impl Api {
+    fn new(forge: &str, base: String) -> Self {
+        Api { base, forge: forge.to_string(), token: OnceCell::new() }
+    }
+
+    /// GET `url` as JSON. The token only goes along when `url` is on this API.
+    pub fn fetch_json<T: serde::de::DeserializeOwned>(&self, client: &Client, url: &str) -> Result<T, String> {
+        let on_api = url.strip_prefix(self.base.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?']));
+        let token = if on_api {
+            self.token.get_or_init(|| auth::token(&self.forge, &self.base)).as_deref()
+        } else {
+            None
+        };
+        http::get_json(client, url, token)
+    }
+}
+
+pub fn open(name: &str, profile: &ForgeProfile) -> Box<dyn Forge> {
+    let (clone, api) = (clone_base(profile), Api::new(name, api_base(profile)));
+    match profile.kind {
+        ForgeKind::Github => Box::new(github::GitHub::new(clone, api)),
+        ForgeKind::Gitlab => Box::new(gitlab::GitLab::new(clone, api)),
+        ForgeKind::Gitea => Box::new(gitea::Gitea::new(clone, api)),
+        ForgeKind::Sourcehut => Box::new(sourcehut::Sourcehut::new(clone)),
+    }
+}
+
+fn profile(config: &GitFetchConfig, name: &str) -> Result<ForgeProfile, String> {
+    let profiles = profiles(config);
+    match profiles.iter().find(|(existing, _)| existing == name) {
+        Some((_, profile)) => Ok(profile.clone()),
+        None => {
+            let names: Vec<&str> = profiles.iter().map(|(name, _)| name.as_str()).collect();
+            Err(format!("Unknown forge {}, known: {}", name, names.join(", ")))
+        }
+    }
+}
+
+pub fn by_name(config: &GitFetchConfig, name: &str) -> Result<Box<dyn Forge>, String> {
+    Ok(open(name, &profile(config, name)?))
+}
+
+/// API base URL of the forge called `name`, for the ones gitfetch talks to
+pub fn api_url(config: &GitFetchConfig, name: &str) -> Result<String, String> {
+    let profile = profile(config, name)?;
+    if profile.kind == ForgeKind::Sourcehut {
+        return Err(format!("{} is a sourcehut forge, gitfetch doesn't use its API", name));
+    }
+    Ok(api_base(&profile))
+}
+
+/// The profile hosting `url` (any transport, on its web or clone host), with its name and the repository path
+fn profile_for_url(config: &GitFetchConfig, url: &str) -> Option<(String, ForgeProfile, String)> {
+    let remote = git::parse_remote(url).ok().filter(|remote| remote.transport != Transport::File)?;
+
+    profiles(config).into_iter().find_map(|(name, profile)| {
+        let path = [profile.web_url.clone(), clone_base(&profile)].iter().find_map(|base| {
+            let base = git::parse_remote(base).ok().filter(|base| base.host == remote.host)?;
+            // Instances can live below a path, e.g. https://example.com/gitlab
+            let path = if base.path.is_empty() {
+                remote.path.as_str()
+            } else {
+                remote.path.strip_prefix(&base.path)?.strip_prefix('/')?
+            };
+            (!path.is_empty()).then(|| path.to_string())
+        })?;
+        Some((name, profile, path))
+    })
+}
+
+/// The forge hosting `url`, as its profile name, the forge and the repository path on it
+pub fn for_url(config: &GitFetchConfig, url: &str) -> Option<(String, Box<dyn Forge>, String)> {
+    let (name, profile, path) = profile_for_url(config, url)?;
+    let forge = open(&name, &profile);
+    Some((name, forge, path))
+}
+
+/// API base and `owner/repo` of a repository on github.com or a GitHub Enterprise profile
+pub fn github_api(config: &GitFetchConfig, url: &str) -> Result<(Api, String), String> {
+    match profile_for_url(config, url) {
+        Some((name, profile, path)) if profile.kind == ForgeKind::Github => {
+            Ok((Api::new(&name, api_base(&profile)), path))
+        }
+        Some((name, _, _)) => Err(format!("{} is on {}, which isn't a GitHub forge", url, name)),
+        None => Err(format!("{} isn't on a configured forge", url)),
+    }
+}
+
+/// Expand `owner/repo` (on the default forge) or `<forge>:path` to a clone URL
+pub fn expand_shorthand(config: &GitFetchConfig, repo: &str) -> Result<String, String> {
+    let (name, path) = repo.split_once(':').unwrap_or((DEFAULT_FORGE, repo));
+
+    let parts: Vec<&str> = path.split('/').collect();
+    if parts.len() < 2 || parts.iter().any(|part| part.is_empty() || *part == "." || *part == "..") {
+        return Err("Invalid repository format".to_string());
+    }
+
+    Ok(by_name(config, name)?.repo_url(path))
+}
+
+/// The shortest shorthand that expands back to `url`
+pub fn shorthand(config: &GitFetchConfig, url: &str) -> Option<String> {
+    let (name, _, path) = for_url(config, url)?;
+    Some(if name == DEFAULT_FORGE { path } else { format!("{}:{}", name, path) })
+}
@@ -0,0 +1,40 @@
+use super::{Forge, RepoInfo};
+use reqwest::blocking::Client;
+
+/// git.sr.ht or a self-hosted instance. Owners are written `~owner`, the `~` may be left out.
+pub struct Sourcehut {
+    web: String,
+}
+
+impl Sourcehut {
+    pub fn new(web: String) -> Self {
+        Sourcehut { web }
+    }
+
+    fn full_path(path: &str) -> String {
+        if path.starts_with('~') {
+            path.to_string()
+        } else {
+            format!("~{}", path)
+        }
+    }
+}
+
+impl Forge for Sourcehut {
+    fn repo_url(&self, path: &str) -> String {
+        format!("{}/{}", self.web, Self::full_path(path))
+    }
+
+    // The sr.ht API is GraphQL behind OAuth, there is nothing to query anonymously
+    fn search(&self, _client: &Client, _query: &str) -> Result<Vec<RepoInfo>, String> {
+        Err("sourcehut has no public search API".to_string())
+    }
+
+    fn metadata(&self, _client: &Client, _path: &str) -> Result<RepoInfo, String> {
+        Err("sourcehut has no public repository API".to_string())
+    }
+
+    fn archive_url(&self, path: &str, commit: &str) -> String {
+        format!("{}/{}/archive/{}.tar.gz", self.web, Self::full_path(path), commit)
+    }
+}
@@ -1,9 +1,15 @@
+use crate::archive;
+use std::path::Path;
 use std::process::Command;
 
-/// Get the current commit hash of a git repository
+/// Get the current commit hash of a git repository (or extracted source archive)
 pub fn get_commit_hash(repo_path: &str) -> Option<String> {
+    if let Some(marker) = archive::read_marker(Path::new(repo_path)) {
+        return Some(marker.commit);
+    }
+
     let output = Command::new("git")
-        .args(&["-C", repo_path, "rev-parse", "HEAD"])
+        .args(["-C", repo_path, "rev-parse", "HEAD"])
         .output()
         .ok()?;
     
@@ -14,10 +20,14 @@ pub fn get_commit_hash(repo_path: &str) -> Option<String> {
     }
 }
 
-/// Get the remote URL of a git repository
+/// Get the remote URL of a git repository (or extracted source archive)
 pub fn get_remote_url(repo_path: &str) -> Option<String> {
+    if let Some(marker) = archive::read_marker(Path::new(repo_path)) {
+        return Some(marker.url);
+    }
+
     let output = Command::new("git")
-        .args(&["-C", repo_path, "config", "--get", "remote.origin.url"])
+        .args(["-C", repo_path, "config", "--get", "remote.origin.url"])
         .output()
         .ok()?;
     
@@ -27,3 +37,248 @@ pub fn get_remote_url(repo_path: &str) -> Option<String> {
         None
     }
 }
+
+/// Set a key in a git config file without running anything from the repo
+pub fn set_config(config_file: &Path, key: &str, value: &str) -> Result<(), String> {
+    let status = Command::new("git")
+        .args(["config", "--file", &config_file.to_string_lossy(), key, value])
+        .status()
+        .map_err(|e| format!("Failed to run git config: {}", e))?;
+    
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to set {} in {}", key, config_file.display()))
+    }
+}
+
+/// Paths of the submodules declared in a checkout's `.gitmodules`
+pub fn submodule_paths(repo_path: &str) -> Vec<String> {
+    let gitmodules = Path::new(repo_path).join(".gitmodules");
+    if !gitmodules.is_file() {
+        return Vec::new();
+    }
+    
+    let output = Command::new("git")
+        .args(["config", "--file", &gitmodules.to_string_lossy(), "--get-regexp", r"^submodule\..*\.path$"])
+        .output();
+    
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once(' ').map(|(_, path)| path.to_string()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// How a remote is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Http,
+    Ssh,
+    File,
+}
+
+/// A remote URL split into the parts gitfetch cares about
+#[derive(Debug, Clone)]
+pub struct RemoteUrl {
+    pub transport: Transport,
+    /// Host name without user or port, `localhost` for file:// remotes
+    pub host: String,
+    /// Path on the host without surrounding slashes or `.git`, absolute for file:// remotes
+    pub path: String,
+}
+
+/// Parse an http(s)://, ssh://, scp-style (`[user@]host:path`) or file:// remote.
+/// Transport helpers such as `ext::`, other schemes and option-like hosts are rejected.
+pub fn parse_remote(url: &str) -> Result<RemoteUrl, String> {
+    // `<helper>::<address>` makes git run `git-remote-<helper>`, e.g. ext:: runs any command
+    if let Some((helper, _)) = url.split_once("::") {
+        if !helper.contains(['/', ':', '[']) {
+            return Err(format!("Transport helper URLs aren't supported: {}", url));
+        }
+    }
+    if url.is_empty() || url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("Invalid URL: {:?}", url));
+    }
+
+    let (transport, authority, path) = match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let transport = match scheme.to_lowercase().as_str() {
+                "https" | "http" => Transport::Http,
+                "ssh" | "git+ssh" | "ssh+git" => Transport::Ssh,
+                "file" => Transport::File,
+                other => return Err(format!("Unsupported URL scheme {}://", other)),
+            };
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            (transport, authority, path)
+        }
+        // git only reads `host:path` as scp-style when the colon comes before any slash,
+        // `[user@host:port]:path` is how scp-style remotes give a port
+        None => match url.strip_prefix('[').and_then(|rest| rest.split_once("]:")) {
+            Some((authority, path)) => (Transport::Ssh, authority, path),
+            None => match url.split_once(':') {
+                Some((authority, path)) if !authority.contains('/') => (Transport::Ssh, authority, path),
+                _ => return Err(format!("Not a URL: {}", url)),
+            },
+        },
+    };
+
+    if transport == Transport::File {
+        if !authority.is_empty() && authority != "localhost" {
+            return Err(format!("file:// URLs can't name a host: {}", url));
+        }
+        let path = format!("/{}", path.trim_end_matches('/'));
+        if path == "/" {
+            return Err(format!("file:// URL has no path: {}", url));
+        }
+        return Ok(RemoteUrl { transport, host: "localhost".to_string(), path });
+    }
+
+    let host_port = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']').map(|(host, _)| host).unwrap_or(bracketed),
+        None => host_port.split_once(':').map(|(host, _)| host).unwrap_or(host_port),
+    };
+    let path = path.trim_matches('/').trim_end_matches(".git").trim_end_matches('/');
+
+    // A leading dash would be read as an option by ssh
+    if host.is_empty() || host.starts_with('-') || !host.chars().all(|c| c.is_ascii_alphanumeric() || "-._:".contains(c)) {
+        return Err(format!("Invalid host in {}", url));
+    }
+    if path.starts_with('-') {
+        return Err(format!("Invalid path in {}", url));
+    }
+
+    Ok(RemoteUrl { transport, host: host.to_lowercase(), path: path.to_string() })
+}
+
+/// `[user@]host:path` as opposed to a `<forge>:path` shorthand: it has a user or a dotted host
+pub fn is_scp_style(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((authority, _)) if !url.contains("://") && !authority.contains('/') => {
+            authority.contains('@') || authority.contains('.') || authority == "localhost"
+        }
+        _ => false,
+    }
+}
+
+/// Derive a `host/owner/name` identity from a remote URL.
+/// Also used as a relative path, so `.` and `..` components are dropped.
+pub fn repo_id(url: &str) -> String {
+    let normalized = match parse_remote(url) {
+        Ok(remote) => format!("{}/{}", remote.host, remote.path),
+        // Not a URL git would accept either, keep something stable to key it by
+        Err(_) => url.split_once("://").map(|(_, rest)| rest).unwrap_or(url).replacen(':', "/", 1),
+    };
+    
+    normalized
+        .trim_end_matches('/')
+        .trim_end_matches(".git")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Host part of a remote URL, as used by the `allowed_hosts` setting
+pub fn repo_host(url: &str) -> String {
+    repo_id(url).split('/').next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_scp_style, parse_remote, repo_host, repo_id, Transport};
+
+    fn parsed(url: &str) -> (Transport, String, String) {
+        let remote = parse_remote(url).unwrap_or_else(|e| panic!("{}", e));
+        (remote.transport, remote.host, remote.path)
+    }
+
+    fn expect(transport: Transport, host: &str, path: &str) -> (Transport, String, String) {
+        (transport, host.to_string(), path.to_string())
+    }
+
+    #[test]
+    fn http_remotes() {
+        assert_eq!(parsed("https://github.com/owner/repo"), expect(Transport::Http, "github.com", "owner/repo"));
+        assert_eq!(parsed("https://github.com/owner/repo.git/"), expect(Transport::Http, "github.com", "owner/repo"));
+        assert_eq!(parsed("http://user:pw@GitHub.com:8080/owner/repo"), expect(Transport::Http, "github.com", "owner/repo"));
+    }
+
+    #[test]
+    fn ssh_remotes() {
+        assert_eq!(parsed("ssh://git@github.com/owner/repo.git"), expect(Transport::Ssh, "github.com", "owner/repo"));
+        assert_eq!(parsed("ssh://git@github.com:2222/owner/repo"), expect(Transport::Ssh, "github.com", "owner/repo"));
+        assert_eq!(parsed("git+ssh://[::1]:2222/owner/repo"), expect(Transport::Ssh, "::1", "owner/repo"));
+    }
+
+    #[test]
+    fn scp_style_remotes() {
+        assert_eq!(parsed("git@github.com:owner/repo.git"), expect(Transport::Ssh, "github.com", "owner/repo"));
+        assert_eq!(parsed("github.com:owner/repo"), expect(Transport::Ssh, "github.com", "owner/repo"));
+        // A port only works in brackets, otherwise it is part of the path
+        assert_eq!(parsed("[git@github.com:2222]:owner/repo.git"), expect(Transport::Ssh, "github.com", "owner/repo"));
+        assert_eq!(parsed("git@github.com:2222/owner/repo"), expect(Transport::Ssh, "github.com", "2222/owner/repo"));
+    }
+
+    #[test]
+    fn file_remotes() {
+        assert_eq!(parsed("file:///srv/git/repo.git"), expect(Transport::File, "localhost", "/srv/git/repo.git"));
+        assert_eq!(parsed("file://localhost/srv/git/repo/"), expect(Transport::File, "localhost", "/srv/git/repo"));
+        assert!(parse_remote("file://other-host/srv/git/repo").is_err());
+        assert!(parse_remote("file:///").is_err());
+    }
+
+    #[test]
+    fn rejected_remotes() {
+        assert!(parse_remote("ext::sh -c touch% /tmp/pwned").is_err());
+        assert!(parse_remote("ssh://-oProxyCommand=evil/repo").is_err());
+        assert!(parse_remote("git@github.com:-repo").is_err());
+        assert!(parse_remote("ftp://github.com/owner/repo").is_err());
+        assert!(parse_remote("owner/repo").is_err());
+        assert!(parse_remote("https://github.com/owner/re po").is_err());
+        assert!(parse_remote("").is_err());
+    }
+
+    #[test]
+    fn scp_style_detection() {
+        assert!(is_scp_style("git@github.com:owner/repo"));
+        assert!(is_scp_style("github.com:owner/repo"));
+        assert!(is_scp_style("localhost:repo"));
+        assert!(is_scp_style("[git@github.com:2222]:owner/repo"));
+        // Forge shorthands and real URLs
+        assert!(!is_scp_style("gitlab:owner/repo"));
+        assert!(!is_scp_style("https://github.com/owner/repo"));
+        assert!(!is_scp_style("./local:path"));
+        assert!(!is_scp_style("owner/repo"));
+    }
+
+    #[test]
+    fn ids_are_the_same_for_every_form() {
+        for url in [
+            "https://github.com/owner/repo",
+            "https://github.com/owner/repo.git",
+            "ssh://git@github.com:22/owner/repo.git",
+            "git@github.com:owner/repo.git",
+            "[git@github.com:22]:owner/repo",
+        ] {
+            assert_eq!(repo_id(url), "github.com/owner/repo", "{}", url);
+        }
+        assert_eq!(repo_id("file:///srv/git/repo.git"), "localhost/srv/git/repo");
+    }
+
+    #[test]
+    fn ids_drop_dot_components() {
+        assert_eq!(repo_id("https://github.com/owner/../../etc/repo"), "github.com/owner/etc/repo");
+        assert_eq!(repo_id("git@github.com:./owner/./repo"), "github.com/owner/repo");
+    }
+
+    #[test]
+    fn hosts() {
+        assert_eq!(repo_host("git@GitHub.com:owner/repo"), "github.com");
+        assert_eq!(repo_host("ssh://git@gitlab.example.com:2222/group/repo"), "gitlab.example.com");
+        assert_eq!(repo_host("file:///srv/git/repo"), "localhost");
+    }
+}
@@ -0,0 +1,244 @@
+use crate::config::GitFetchConfig;
+use crate::types::CachedResponse;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::HeaderMap;
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Errors from `get_json` that callers tell apart. Rate limit errors go on to say when to retry.
+pub const NOT_FOUND: &str = "not found";
+pub const RATE_LIMITED: &str = "rate limited";
+
+/// Longest rate limit worth sitting out, anything longer is reported instead
+const MAX_WAIT_SECS: u64 = 60;
+/// Requests made for one call, counting retries after rate limits, 5xx and network errors
+const ATTEMPTS: u32 = 4;
+
+pub fn client() -> Client {
+    Client::builder()
+        .user_agent("gitfetch/0.18")
+        .build()
+        .expect("Can't create HTTP client")
+}
+
+/// GET `url` as JSON. A cached copy is revalidated by its ETag, short rate limits are waited
+/// out and server or network errors retried with backoff.
+pub fn get_json<T: serde::de::DeserializeOwned>(client: &Client, url: &str, token: Option<&str>) -> Result<T, String> {
+    let body = get_cached(client, url, token)?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid response: {}", e))
+}
+
+fn get_cached(client: &Client, url: &str, token: Option<&str>) -> Result<String, String> {
+    let cache_file = cache_path(url, token);
+    let cached: Option<CachedResponse> = fs::read_to_string(&cache_file)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .filter(|cached: &CachedResponse| cached.url == url);
+
+    let mut attempt = 1;
+    loop {
+        let mut request = client.get(url).header("Accept", "application/json");
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(cached) = &cached {
+            request = request.header("If-None-Match", &cached.etag);
+        }
+
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(e) if attempt < ATTEMPTS => {
+                wait(backoff_secs(attempt), &format!("Network error ({})", e));
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(format!("Network error: {}", e)),
+        };
+
+        let status = response.status();
+        match status.as_u16() {
+            304 => {
+                if let Some(cached) = cached {
+                    return Ok(cached.body);
+                }
+            }
+            200..=299 => {
+                let etag = header(response.headers(), "etag");
+                let body = response.text().map_err(|e| format!("Invalid response: {}", e))?;
+                if let Some(etag) = etag {
+                    store(&cache_file, CachedResponse { url: url.to_string(), etag, body: body.clone() });
+                }
+                return Ok(body);
+            }
+            404 => return Err(NOT_FOUND.to_string()),
+            403 | 429 if is_rate_limited(&response) => {
+                let retry_after = retry_after_secs(response.headers(), chrono::Utc::now().timestamp());
+                if attempt >= ATTEMPTS || retry_after.is_some_and(|secs| secs > MAX_WAIT_SECS) {
+                    return Err(rate_limit_message(retry_after));
+                }
+                let host = response.url().host_str().unwrap_or("The server").to_string();
+                wait(retry_after.unwrap_or_else(|| backoff_secs(attempt)), &format!("{} is rate limiting", host));
+                attempt += 1;
+                continue;
+            }
+            500..=599 if attempt < ATTEMPTS => {
+                wait(backoff_secs(attempt), &format!("HTTP {}", status));
+                attempt += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        return Err(format!("HTTP {}", status));
+    }
+}
+
+/// Cache entries are per URL and per token, so responses only ever go back to whoever could see them
+fn cache_path(url: &str, token: Option<&str>) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(token.unwrap_or_default().as_bytes());
+    GitFetchConfig::http_cache_root().join(format!("{:x}.json", hasher.finalize()))
+}
+
+/// A failed cache write only costs a full request next time. Bodies of authenticated
+/// requests end up here, so entries are private like the tokens file.
+fn store(path: &Path, response: CachedResponse) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let Ok(contents) = serde_json::to_string(&response) else { return };
+
+    let temp = path.with_extension("json.tmp");
+    let _ = fs::remove_file(&temp);
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&temp)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .and_then(|_| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name)?.to_str().ok().map(str::to_string)
+}
+
+/// 429, or a 403 that comes with rate limit headers (GitHub answers both limits with 403)
+fn is_rate_limited(response: &Response) -> bool {
+    let headers = response.headers();
+    let remaining = header(headers, "x-ratelimit-remaining").or_else(|| header(headers, "ratelimit-remaining"));
+    response.status().as_u16() == 429
+        || header(headers, "retry-after").is_some()
+        || remaining.as_deref() == Some("0")
+}
+
+/// Seconds from `now` (a Unix timestamp) until the server takes requests again,
+/// from Retry-After or the rate limit reset time
+fn retry_after_secs(headers: &HeaderMap, now: i64) -> Option<u64> {
+    if let Some(retry_after) = header(headers, "retry-after") {
+        if let Ok(secs) = retry_after.trim().parse::<u64>() {
+            return Some(secs);
+        }
+        // Retry-After may also be an HTTP date
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(retry_after.trim()) {
+            return Some((date.timestamp() - now).max(0) as u64);
+        }
+    }
+
+    let reset = header(headers, "x-ratelimit-reset").or_else(|| header(headers, "ratelimit-reset"))?;
+    let reset: i64 = reset.trim().parse().ok()?;
+    // Reset is an epoch time on GitHub and GitLab, a delay in seconds under the IETF draft
+    let secs = if reset > 1_000_000_000 { reset - now } else { reset };
+    // The reset second itself may still be limited
+    Some(secs.max(0) as u64 + 1)
+}
+
+fn rate_limit_message(retry_after: Option<u64>) -> String {
+    match retry_after {
+        Some(secs) => {
+            let until = chrono::Local::now() + chrono::Duration::seconds(secs as i64);
+            format!("{}, try again at {} (in {})", RATE_LIMITED, until.format("%H:%M:%S"), format_wait(secs))
+        }
+        None => format!("{}, the server didn't say for how long", RATE_LIMITED),
+    }
+}
+
+fn format_wait(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn backoff_secs(attempt: u32) -> u64 {
+    1 << attempt
+}
+
+fn wait(secs: u64, reason: &str) {
+    eprintln!("⏳ {}, retrying in {}s", reason, secs);
+    thread::sleep(Duration::from_secs(secs));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::retry_after_secs;
+    use reqwest::header::HeaderMap;
+
+    /// Fri, 15 Jan 2027 08:00:00 GMT
+    const NOW: i64 = 1_800_000_000;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        assert_eq!(retry_after_secs(&headers(&[("retry-after", "120")]), NOW), Some(120));
+        assert_eq!(retry_after_secs(&headers(&[("retry-after", " 0 ")]), NOW), Some(0));
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let later = headers(&[("retry-after", "Fri, 15 Jan 2027 08:00:30 GMT")]);
+        assert_eq!(retry_after_secs(&later, NOW), Some(30));
+        // A date in the past means now
+        let earlier = headers(&[("retry-after", "Fri, 15 Jan 2027 07:59:00 GMT")]);
+        assert_eq!(retry_after_secs(&earlier, NOW), Some(0));
+    }
+
+    #[test]
+    fn retry_after_beats_reset() {
+        let both = headers(&[("retry-after", "5"), ("x-ratelimit-reset", &(NOW + 600).to_string())]);
+        assert_eq!(retry_after_secs(&both, NOW), Some(5));
+    }
+
+    #[test]
+    fn reset_as_epoch_or_delay() {
+        let epoch = headers(&[("x-ratelimit-reset", &(NOW + 90).to_string())]);
+        assert_eq!(retry_after_secs(&epoch, NOW), Some(91));
+        let delay = headers(&[("ratelimit-reset", "30")]);
+        assert_eq!(retry_after_secs(&delay, NOW), Some(31));
+    }
+
+    #[test]
+    fn missing_or_invalid_headers() {
+        assert_eq!(retry_after_secs(&HeaderMap::new(), NOW), None);
+        assert_eq!(retry_after_secs(&headers(&[("retry-after", "soon")]), NOW), None);
+        assert_eq!(retry_after_secs(&headers(&[("x-ratelimit-reset", "never")]), NOW), None);
+    }
+}
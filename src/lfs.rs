@@ -0,0 +1,449 @@
+use crate::config::GitFetchConfig;
+use crate::git::{self, Transport};
+use crate::http;
+use crate::security;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const POINTER_HEADER: &str = "version https://git-lfs.github.com/spec/v1\n";
+/// Pointer files are tiny, anything bigger is real content
+const MAX_POINTER_SIZE: u64 = 1024;
+/// Objects requested per batch API call
+const BATCH_SIZE: usize = 100;
+/// Verified objects by oid, relative to the checkout
+const CACHE_DIR: &str = ".git/lfs/objects";
+/// Objects for the sandboxed download to fetch, relative to the checkout
+const REQUEST_FILE: &str = ".git/lfs/request.json";
+
+/// An LFS pointer file left in the checkout
+#[derive(Debug, Clone)]
+pub struct LfsPointer {
+    pub path: PathBuf,
+    pub oid: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct LfsReport {
+    pub materialized: usize,
+    /// Pointers left in place because the object is over the size limit
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    operation: &'a str,
+    transfers: [&'a str; 1],
+    objects: Vec<BatchObject>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct BatchObject {
+    oid: String,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    objects: Vec<BatchResult>,
+}
+
+#[derive(Deserialize)]
+struct BatchResult {
+    oid: String,
+    actions: Option<BatchActions>,
+    error: Option<BatchError>,
+}
+
+#[derive(Deserialize)]
+struct BatchActions {
+    download: Option<BatchAction>,
+}
+
+#[derive(Deserialize)]
+struct BatchAction {
+    href: String,
+    #[serde(default)]
+    header: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct BatchError {
+    message: String,
+}
+
+/// Whether the checkout's top-level `.gitattributes` routes anything through LFS
+pub fn uses_lfs(repo_path: &Path) -> bool {
+    fs::read_to_string(repo_path.join(".gitattributes"))
+        .is_ok_and(|attributes| attributes.contains("filter=lfs"))
+}
+
+/// All pointer files in the checkout, not descending into hidden dirs or submodules
+pub fn find_pointers(repo_path: &Path) -> Vec<LfsPointer> {
+    let mut pointers = Vec::new();
+    collect_pointers(repo_path, &mut pointers);
+    pointers.sort_by(|a, b| a.path.cmp(&b.path));
+    pointers
+}
+
+fn collect_pointers(dir: &Path, pointers: &mut Vec<LfsPointer>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        // DirEntry metadata doesn't follow symlinks, so symlinks are neither dirs nor files here
+        match entry.metadata() {
+            // Submodules have their own LFS server
+            Ok(metadata) if metadata.is_dir() && !path.join(".git").exists() => {
+                collect_pointers(&path, pointers);
+            }
+            Ok(metadata) if metadata.is_file() && metadata.len() <= MAX_POINTER_SIZE => {
+                if let Some((oid, size)) = read_pointer(&path) {
+                    pointers.push(LfsPointer { path, oid, size });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Open a file in the checkout without following a symlink someone may have put in its place
+fn open_in_checkout(path: &Path, options: &mut fs::OpenOptions) -> io::Result<fs::File> {
+    let file = options.custom_flags(libc::O_NOFOLLOW).open(path)?;
+    if !file.metadata()?.is_file() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a regular file"));
+    }
+    Ok(file)
+}
+
+fn read_pointer(path: &Path) -> Option<(String, u64)> {
+    let mut bytes = Vec::new();
+    open_in_checkout(path, fs::OpenOptions::new().read(true))
+        .ok()?
+        .take(MAX_POINTER_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    parse_pointer(&bytes)
+}
+
+/// Parse a pointer file into its SHA-256 oid and object size
+pub fn parse_pointer(bytes: &[u8]) -> Option<(String, u64)> {
+    if bytes.len() as u64 > MAX_POINTER_SIZE {
+        return None;
+    }
+    let text = std::str::from_utf8(bytes).ok()?;
+    let body = text.strip_prefix(POINTER_HEADER)?;
+
+    let mut oid = None;
+    let mut size = None;
+    for line in body.lines() {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+
+    let oid = oid.filter(|oid| oid.len() == 64 && oid.chars().all(|c| c.is_ascii_hexdigit()))?;
+    Some((oid.to_lowercase(), size?))
+}
+
+/// Download the LFS objects behind every pointer in the checkout, verify each against
+/// its pointer's SHA-256 and size, and write the content over the pointer file.
+/// Objects over `max_size` bytes are left as pointers. The download itself runs in the
+/// network sandbox (`fetch_requested`), only verified objects leave it.
+pub fn materialize(repo_path: &Path, remote_url: &str, max_size: u64) -> Result<LfsReport, String> {
+    let mut report = LfsReport::default();
+    let pointers = find_pointers(repo_path);
+    if pointers.is_empty() {
+        return Ok(report);
+    }
+
+    let cache_dir = repo_path.join(CACHE_DIR);
+    let mut wanted: Vec<BatchObject> = Vec::new();
+
+    for pointer in &pointers {
+        if pointer.size > max_size {
+            report.skipped.push(relative(repo_path, &pointer.path));
+        } else if !cache_path(&cache_dir, &pointer.oid).exists()
+            && !wanted.iter().any(|object| object.oid == pointer.oid)
+        {
+            wanted.push(BatchObject { oid: pointer.oid.clone(), size: pointer.size });
+        }
+    }
+
+    if !wanted.is_empty() {
+        let endpoint = lfs_endpoint(repo_path, remote_url)?;
+        let request = repo_path.join(REQUEST_FILE);
+        fs::create_dir_all(&cache_dir).map_err(|e| format!("Can't create LFS cache: {}", e))?;
+        fs::write(&request, serde_json::to_string(&wanted).expect("Can't serialize LFS request"))
+            .map_err(|e| format!("Can't write {}: {}", request.display(), e))?;
+
+        println!("Downloading {} LFS objects from {}", wanted.len(), endpoint);
+        let fetched = security::run_sandboxed_self(repo_path, &["lfs-fetch", &endpoint], true);
+        let _ = fs::remove_file(&request);
+        fetched.map_err(|e| format!("LFS download failed: {}", e))?;
+    }
+
+    for pointer in &pointers {
+        if pointer.size > max_size {
+            continue;
+        }
+        let object = cache_path(&cache_dir, &pointer.oid);
+        verify_cached(&object, pointer)?;
+
+        // Write into the existing file so its mode (e.g. executable) is kept
+        let mut source = fs::File::open(&object)
+            .map_err(|e| format!("Missing LFS object {}: {}", pointer.oid, e))?;
+        let mut dest = open_in_checkout(&pointer.path, fs::OpenOptions::new().write(true).truncate(true))
+            .map_err(|e| format!("Can't write {}: {}", pointer.path.display(), e))?;
+        io::copy(&mut source, &mut dest)
+            .map_err(|e| format!("Can't write {}: {}", pointer.path.display(), e))?;
+        report.materialized += 1;
+    }
+
+    Ok(report)
+}
+
+/// Network stage of `materialize`, run by `gitfetch lfs-fetch` inside the sandbox with the
+/// checkout as working directory: download the objects listed in `REQUEST_FILE` from
+/// `endpoint` into the LFS cache, verifying each against its pointer
+pub fn fetch_requested(endpoint: &str) -> Result<usize, String> {
+    let request = fs::read_to_string(REQUEST_FILE)
+        .map_err(|e| format!("Can't read {}: {}", REQUEST_FILE, e))?;
+    let wanted: Vec<BatchObject> = serde_json::from_str(&request)
+        .map_err(|e| format!("Invalid {}: {}", REQUEST_FILE, e))?;
+
+    let client = http::client();
+    for chunk in wanted.chunks(BATCH_SIZE) {
+        download_batch(&client, endpoint, chunk, Path::new(CACHE_DIR))?;
+    }
+    Ok(wanted.len())
+}
+
+/// Check a cached object against its pointer again before it goes into the checkout,
+/// the sandbox had write access to the cache
+fn verify_cached(object: &Path, pointer: &LfsPointer) -> Result<(), String> {
+    let mut file = fs::File::open(object)
+        .map_err(|e| format!("Missing LFS object {}: {}", pointer.oid, e))?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Can't read LFS object {}: {}", pointer.oid, e))?;
+
+    if size != pointer.size || format!("{:x}", hasher.finalize()) != pointer.oid {
+        let _ = fs::remove_file(object);
+        return Err(format!("LFS object {} in the cache doesn't match its pointer", pointer.oid));
+    }
+    Ok(())
+}
+
+fn download_batch(
+    client: &reqwest::blocking::Client,
+    endpoint: &str,
+    objects: &[BatchObject],
+    cache_dir: &Path,
+) -> Result<(), String> {
+    let request = BatchRequest {
+        operation: "download",
+        transfers: ["basic"],
+        objects: objects.to_vec(),
+    };
+
+    let response = client
+        .post(format!("{}/objects/batch", endpoint))
+        .header("Accept", "application/vnd.git-lfs+json")
+        .header("Content-Type", "application/vnd.git-lfs+json")
+        .json(&request)
+        .send()
+        .map_err(|e| format!("LFS batch request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("LFS batch request failed: HTTP {}", response.status()));
+    }
+
+    let batch: BatchResponse = response.json()
+        .map_err(|e| format!("Invalid LFS batch response: {}", e))?;
+
+    for object in objects {
+        let result = batch.objects.iter()
+            .find(|result| result.oid == object.oid)
+            .ok_or_else(|| format!("LFS server didn't answer for {}", object.oid))?;
+
+        if let Some(error) = &result.error {
+            return Err(format!("LFS object {}: {}", object.oid, error.message));
+        }
+
+        let action = result.actions.as_ref()
+            .and_then(|actions| actions.download.as_ref())
+            .ok_or_else(|| format!("LFS server has no download for {}", object.oid))?;
+
+        download_object(client, action, object, cache_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Stream one object into the cache, refusing anything that doesn't match its pointer
+fn download_object(
+    client: &reqwest::blocking::Client,
+    action: &BatchAction,
+    object: &BatchObject,
+    cache_dir: &Path,
+) -> Result<(), String> {
+    let mut request = client.get(&action.href);
+    for (name, value) in &action.header {
+        request = request.header(name, value);
+    }
+
+    let response = request.send()
+        .map_err(|e| format!("Download of {} failed: {}", object.oid, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Download of {} failed: HTTP {}", object.oid, response.status()));
+    }
+
+    let dest = cache_path(cache_dir, &object.oid);
+    let partial = dest.with_extension("part");
+    fs::create_dir_all(dest.parent().unwrap())
+        .map_err(|e| format!("Can't create LFS cache: {}", e))?;
+    let mut file = fs::File::create(&partial)
+        .map_err(|e| format!("Can't create {}: {}", partial.display(), e))?;
+
+    // Read one byte past the expected size so an oversized body is caught
+    let mut body = response.take(object.size + 1);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    let mut received = 0u64;
+
+    loop {
+        let read = body.read(&mut buffer)
+            .map_err(|e| format!("Download of {} failed: {}", object.oid, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        received += read as u64;
+        file.write_all(&buffer[..read])
+            .map_err(|e| format!("Can't write {}: {}", partial.display(), e))?;
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if received != object.size || actual != object.oid {
+        let _ = fs::remove_file(&partial);
+        return Err(format!(
+            "LFS object {} failed verification ({} bytes, sha256 {})",
+            object.oid, received, actual
+        ));
+    }
+
+    fs::rename(&partial, &dest).map_err(|e| format!("Can't store {}: {}", dest.display(), e))
+}
+
+/// Batch API endpoint: `lfs.url` from `.lfsconfig`, or `<remote>.git/info/lfs`
+fn lfs_endpoint(repo_path: &Path, remote_url: &str) -> Result<String, String> {
+    if let Some(url) = configured_lfs_url(repo_path) {
+        // The repo picks this URL, so keep it on the remote's host or a host the user allowed explicitly
+        let host = git::repo_host(&url);
+        if host == git::repo_host(remote_url) || GitFetchConfig::load().allowed_hosts.contains(&host) {
+            return Ok(url);
+        }
+        println!("⚠️  Ignoring lfs.url {} from .lfsconfig, {} is neither the remote's host nor in allowed_hosts", url, host);
+    }
+
+    // Like git-lfs, SSH remotes use the HTTPS endpoint of the same host and path
+    let remote_url = match git::parse_remote(remote_url) {
+        Ok(remote) if remote.transport == Transport::Http => remote_url.to_string(),
+        Ok(remote) if remote.transport == Transport::Ssh => format!("https://{}/{}", remote.host, remote.path),
+        _ => return Err(format!("LFS downloads need an http(s) or ssh remote, not {}", remote_url)),
+    };
+
+    let base = remote_url.trim_end_matches('/');
+    if base.ends_with(".git") {
+        Ok(format!("{}/info/lfs", base))
+    } else {
+        Ok(format!("{}.git/info/lfs", base))
+    }
+}
+
+fn configured_lfs_url(repo_path: &Path) -> Option<String> {
+    let lfsconfig = repo_path.join(".lfsconfig");
+    if !lfsconfig.is_file() {
+        return None;
+    }
+
+    let output = Command::new("git")
+        .args(["config", "--file", &lfsconfig.to_string_lossy(), "--get", "lfs.url"])
+        .output()
+        .ok()?;
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && url.starts_with("https://")).then(|| url.trim_end_matches('/').to_string())
+}
+
+fn cache_path(cache_dir: &Path, oid: &str) -> PathBuf {
+    cache_dir.join(&oid[0..2]).join(&oid[2..4]).join(oid)
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_pointer, MAX_POINTER_SIZE, POINTER_HEADER};
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    fn pointer(body: &str) -> Vec<u8> {
+        format!("{}{}", POINTER_HEADER, body).into_bytes()
+    }
+
+    #[test]
+    fn valid_pointer() {
+        let bytes = pointer(&format!("oid sha256:{}\nsize 12345\n", OID));
+        assert_eq!(parse_pointer(&bytes), Some((OID.to_string(), 12345)));
+    }
+
+    #[test]
+    fn oid_is_lowercased() {
+        let bytes = pointer(&format!("oid sha256:{}\nsize 1\n", OID.to_uppercase()));
+        assert_eq!(parse_pointer(&bytes), Some((OID.to_string(), 1)));
+    }
+
+    #[test]
+    fn extension_lines_are_ignored() {
+        let bytes = pointer(&format!("ext-0-foo sha256:{}\noid sha256:{}\nsize 7\n", OID, OID));
+        assert_eq!(parse_pointer(&bytes), Some((OID.to_string(), 7)));
+    }
+
+    #[test]
+    fn oversized_pointer() {
+        let padding = "x".repeat(MAX_POINTER_SIZE as usize);
+        let bytes = pointer(&format!("oid sha256:{}\nsize 7\n{}\n", OID, padding));
+        assert_eq!(parse_pointer(&bytes), None);
+    }
+
+    #[test]
+    fn malformed_pointers() {
+        // Wrong or missing header
+        assert_eq!(parse_pointer(format!("oid sha256:{}\nsize 7\n", OID).as_bytes()), None);
+        assert_eq!(parse_pointer(format!("version https://example.com/v2\noid sha256:{}\nsize 7\n", OID).as_bytes()), None);
+        // Missing or bad fields
+        assert_eq!(parse_pointer(&pointer("size 7\n")), None);
+        assert_eq!(parse_pointer(&pointer(&format!("oid sha256:{}\n", OID))), None);
+        assert_eq!(parse_pointer(&pointer(&format!("oid sha256:{}\nsize -1\n", OID))), None);
+        assert_eq!(parse_pointer(&pointer(&format!("oid sha256:{}\nsize 7\n", &OID[1..]))), None);
+        assert_eq!(parse_pointer(&pointer(&format!("oid sha256:{}g\nsize 7\n", &OID[1..]))), None);
+        assert_eq!(parse_pointer(&pointer(&format!("oid md5:{}\nsize 7\n", OID))), None);
+        // Not UTF-8
+        assert_eq!(parse_pointer(&[0xff, 0xfe, 0x00]), None);
+    }
+}
@@ -1,20 +1,28 @@
 use clap::{Parser, Subcommand, CommandFactory, ValueHint};
 use clap_complete::Shell;
+use std::path::PathBuf;
 
 mod types;
 mod config;
 mod git;
 mod checksum;
 mod security;
+mod submodules;
+mod lfs;
+mod mirror;
+mod archive;
+mod forge;
+mod auth;
+mod http;
 mod commands;
 
 use commands::*;
+use commands::clone::CloneOptions;
 
 #[derive(Parser)]
 #[command(name = "gitfetch")]
 #[command(about = "A GitHub Package Manager from Hell", long_about = None)]
 #[command(version = env!("CARGO_PKG_VERSION"))]
-
 #[command(after_help = "TRUST MODES (for clone command):\n  \
     paranoid  - Maximum security: verify everything, prompt for all decisions, isolate network\n  \
     normal    - Default: balanced security with reasonable prompts and standard checks\n  \
@@ -28,11 +36,11 @@ pub struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Clone a repository with ACTUAL security (hooks disabled, network isolated)
-    #[command(short_flag = 'c', visible_alias = "clone")]
+    #[command(short_flag = 'c')]
     Clone {
-        /// Repository URL (e.g., https://github.com/user/repo)
-        #[arg(value_hint = ValueHint::Url)]
-        repo: String,
+        /// Repository URLs (https://, ssh://, git@host:path or file://) or shorthands (user/repo, gitlab:group/repo)
+        #[arg(value_hint = ValueHint::Url, required = true)]
+        repos: Vec<String>,
         /// Verify against known checksums (requires checksum registry)
         #[arg(long, short = 'v')]
         verify_checksum: bool,
@@ -49,18 +57,51 @@ enum Commands {
                           gitfetch clone https://github.com/user/repo --trust-mode paranoid\n  \
                           gitfetch clone https://github.com/user/repo --trust-mode yolo")]
         trust_mode: String,
+        /// Tag, branch or full commit SHA to check out instead of the default branch
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+        /// Shallow clone with only this many commits of history
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        depth: Option<u32>,
+        /// Partial clone filter, e.g. blob:none to fetch file contents only for the checkout
+        #[arg(long, value_name = "FILTER")]
+        filter: Option<String>,
+        /// Only check out these directories (comma separated or repeated)
+        #[arg(long, value_name = "PATH", num_args = 1.., value_delimiter = ',')]
+        sparse: Vec<String>,
+        /// Submodules: none (default), prompt for each, or recursive (trust mode decides prompts)
+        #[arg(long, default_value = "none", value_parser = ["none", "prompt", "recursive"])]
+        submodules: String,
+        /// Download Git LFS content instead of leaving pointer files
+        #[arg(long)]
+        lfs: bool,
+        /// Skip LFS files larger than this many MB
+        #[arg(long, value_name = "MB", default_value_t = 100, requires = "lfs")]
+        lfs_max_size: u64,
+        /// How many repositories to clone at once
+        #[arg(long, short = 'j', default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+        /// Never use the network, clone from the local mirror cache or --bundle
+        #[arg(long, conflicts_with_all = ["depth", "filter", "submodules", "lfs"])]
+        offline: bool,
+        /// Git bundle to clone from instead of the cache
+        #[arg(long, value_name = "FILE", requires = "offline", value_hint = ValueHint::FilePath)]
+        bundle: Option<PathBuf>,
+        /// Download a source tarball of the commit instead of cloning (no git history)
+        #[arg(long, conflicts_with_all = ["depth", "filter", "sparse", "submodules", "lfs", "offline"])]
+        archive: bool,
     },
     /// List all the repos you've installed with this nonsense
-    #[command(short_flag = 'l', visible_alias = "list")]
+    #[command(short_flag = 'l')]
     List,
     /// Search for repositories by name
-    #[command(short_flag = 's', visible_alias = "search")]
+    #[command(short_flag = 's')]
     Search {
-        /// Repository name to search for
+        /// Repository name to search for, or an exact owner/repo; prefix a forge to search it (gitlab:name)
         query: String,
     },
     /// Print something utterly pointless
-    #[command(short_flag = 'e', visible_alias = "easter-egg")]
+    #[command(short_flag = 'e')]
     EasterEgg,
     /// Generate shell completion scripts
     Completions {
@@ -69,7 +110,6 @@ enum Commands {
         shell: Shell,
     },
     /// Calculate checksums for a cloned repository
-    #[command(visible_alias = "checksum")]
     Checksum {
         /// Path to the repository
         path: String,
@@ -78,10 +118,134 @@ enum Commands {
         save: bool,
     },
     /// Verify repository integrity against saved checksums
-    #[command(visible_alias = "verify")]
     Verify {
-        /// Path to the repository
+        /// Paths to repositories, or names of installed ones
+        #[arg(required_unless_present = "all")]
+        targets: Vec<String>,
+        /// Verify every installed repository
+        #[arg(long, short = 'a', conflicts_with = "targets")]
+        all: bool,
+        /// How many repositories to verify at once
+        #[arg(long, short = 'j', default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+    },
+    /// Fetch and install the latest commit of installed repositories
+    Update {
+        /// Installed repositories: name, owner/name or host/owner/name
+        #[arg(required_unless_present = "all")]
+        names: Vec<String>,
+        /// Update every installed repository
+        #[arg(long, short = 'a', conflicts_with = "names")]
+        all: bool,
+        /// How many repositories to update at once
+        #[arg(long, short = 'j', default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+        /// Trust mode: paranoid (max security), normal (default), yolo (minimal prompts)
+        #[arg(long, default_value = "normal", value_parser = ["paranoid", "normal", "yolo"])]
+        trust_mode: String,
+    },
+    /// Uninstall a repository and clean up its workspace
+    #[command(visible_alias = "uninstall")]
+    Remove {
+        /// Installed repository: name, owner/name or host/owner/name
+        name: String,
+        /// Also drop the repository's checksum registry entry
+        #[arg(long)]
+        forget_checksum: bool,
+        /// Don't ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Export or reproduce the exact set of installed repositories
+    Lock {
+        #[command(subcommand)]
+        command: LockCommands,
+    },
+    /// Clone or re-pin repos declared in a gitfetch.toml manifest
+    Sync {
+        /// Manifest to read
+        #[arg(long, short = 'f', default_value = "gitfetch.toml", value_hint = ValueHint::FilePath)]
+        file: String,
+        /// Also fetch upstream changes for repos that already match the manifest
+        #[arg(long, short = 'u')]
+        update: bool,
+    },
+    /// Restore a previously installed version of a repository
+    Rollback {
+        /// Installed repository: name, owner/name or host/owner/name
+        name: String,
+        /// Commit (or unique prefix) to restore, defaults to the most recent saved version
+        #[arg(long, value_name = "COMMIT")]
+        to: Option<String>,
+        /// Show the saved versions instead of restoring one
+        #[arg(long, conflicts_with = "to")]
+        list: bool,
+    },
+    /// Show installed repos whose remote ref moved past the recorded commit
+    Outdated {
+        /// Only check these installed repositories, defaults to all of them
+        names: Vec<String>,
+        /// How many remotes to query at once
+        #[arg(long, short = 'j', default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show everything gitfetch knows about one installed repository
+    Info {
+        /// Installed repository: name, owner/name or host/owner/name
+        name: String,
+    },
+    /// Delete orphaned or oversized workspaces
+    Gc {
+        /// Only list what would be deleted
+        #[arg(long, short = 'n')]
+        dry_run: bool,
+        /// Also delete workspaces of installed repos larger than this many MB
+        #[arg(long, value_name = "MB")]
+        max_size: Option<u64>,
+        /// Don't ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Check the sandbox, tools and gitfetch state for problems
+    Doctor,
+    /// Bring an existing local clone under gitfetch management
+    Adopt {
+        /// Path to the existing clone
+        #[arg(value_hint = ValueHint::DirPath)]
         path: String,
+        /// Trust mode: paranoid (max security), normal (default), yolo (minimal prompts)
+        #[arg(long, default_value = "normal", value_parser = ["paranoid", "normal", "yolo"])]
+        trust_mode: String,
+    },
+    /// Download a release asset and verify its SHA-256 digest
+    Release {
+        /// Repository (e.g., user/repo or https://github.com/user/repo)
+        repo: String,
+        /// Release tag, defaults to the latest release
+        #[arg(long)]
+        tag: Option<String>,
+        /// Asset name, `*` matches anything (defaults to the only asset for this platform)
+        #[arg(long, value_name = "NAME")]
+        asset: Option<String>,
+        /// Directory to put the asset in, defaults to the current one
+        #[arg(long, short = 'o', value_name = "DIR", value_hint = ValueHint::DirPath)]
+        output: Option<String>,
+        /// Trust mode: paranoid (max security), normal (default), yolo (minimal prompts)
+        #[arg(long, default_value = "normal", value_parser = ["paranoid", "normal", "yolo"])]
+        trust_mode: String,
+    },
+    /// Save, remove or show API tokens used for search, lookups and releases
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommands,
+    },
+    /// Internal command for the sandboxed LFS download, run inside bubblewrap
+    #[command(hide = true)]
+    LfsFetch {
+        endpoint: String,
     },
     /// Internal command for completion suggestions
     #[command(hide = true)]
@@ -92,6 +256,46 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum LockCommands {
+    /// Write every installed repo's URL, ref, commit and total hash to a lockfile
+    Export {
+        /// Lockfile to write
+        #[arg(long, short = 'o', default_value = "gitfetch.lock", value_hint = ValueHint::FilePath)]
+        output: String,
+    },
+    /// Clone every locked repo at its pinned commit and check its total hash
+    Install {
+        /// Lockfile to read
+        #[arg(long, short = 'f', default_value = "gitfetch.lock", value_hint = ValueHint::FilePath)]
+        file: String,
+        /// Trust mode: paranoid (max security), normal (default), yolo (minimal prompts)
+        #[arg(long, default_value = "normal", value_parser = ["paranoid", "normal", "yolo"])]
+        trust_mode: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Read a token from stdin and save it for a forge's API
+    Login {
+        /// Forge the token is for, as in `<forge>:owner/repo`
+        #[arg(default_value = forge::DEFAULT_FORGE)]
+        forge: String,
+        /// Hand the token to git's credential helper instead of saving it in tokens.json
+        #[arg(long)]
+        git_credential: bool,
+    },
+    /// Forget the saved token of a forge
+    Logout {
+        /// Forge whose token to remove
+        #[arg(default_value = forge::DEFAULT_FORGE)]
+        forge: String,
+    },
+    /// Show which forges have a token and where it comes from
+    Status,
+}
+
 fn display_banner() {
     println!(r#"
  ██████╗ ██╗████████╗███████╗███████╗████████╗ ██████╗██╗  ██╗
@@ -112,17 +316,59 @@ fn main() {
 
     match cli.command {
         None => display_banner(),
-        Some(Commands::Clone { repo, verify_checksum, trust_mode }) => {
-            clone_repo(&repo, verify_checksum, &trust_mode)
+        Some(Commands::Clone {
+            repos, verify_checksum, trust_mode, git_ref, depth, filter, sparse, submodules, lfs, lfs_max_size, jobs,
+            offline, bundle, archive,
+        }) => {
+            clone_repo(&repos, jobs as usize, &CloneOptions {
+                verify_checksum,
+                trust_mode: &trust_mode,
+                git_ref: git_ref.as_deref(),
+                depth,
+                filter: filter.as_deref(),
+                sparse_paths: &sparse,
+                submodules: Some(&submodules),
+                lfs_max_size: lfs.then_some(lfs_max_size),
+                offline,
+                bundle: bundle.as_deref(),
+                archive,
+                ..Default::default()
+            })
         }
         Some(Commands::List) => list_repos(),
         Some(Commands::Search { query }) => search_repos(&query),
         Some(Commands::EasterEgg) => easter_egg(),
         Some(Commands::Completions { shell }) => generate_completions(shell),
+        Some(Commands::LfsFetch { endpoint }) => lfs_fetch(&endpoint),
         Some(Commands::Complete { completion_type, partial }) => {
             complete_suggestions(&completion_type, &partial)
         }
         Some(Commands::Checksum { path, save }) => checksum_command(&path, save),
-        Some(Commands::Verify { path }) => verify_command(&path),
+        Some(Commands::Verify { targets, all, jobs }) => verify_command(&targets, all, jobs as usize),
+        Some(Commands::Update { names, all, jobs, trust_mode }) => {
+            update_repos(&names, all, jobs as usize, &trust_mode)
+        }
+        Some(Commands::Remove { name, forget_checksum, yes }) => {
+            remove_repo(&name, forget_checksum, yes)
+        }
+        Some(Commands::Lock { command: LockCommands::Export { output } }) => lock_export(&output),
+        Some(Commands::Lock { command: LockCommands::Install { file, trust_mode } }) => {
+            lock_install(&file, &trust_mode)
+        }
+        Some(Commands::Sync { file, update }) => sync_repos(&file, update),
+        Some(Commands::Rollback { name, to, list }) => rollback_repo(&name, to.as_deref(), list),
+        Some(Commands::Outdated { names, jobs, json }) => outdated_repos(&names, jobs as usize, json),
+        Some(Commands::Info { name }) => repo_info(&name),
+        Some(Commands::Gc { dry_run, max_size, yes }) => gc_workspaces(dry_run, max_size, yes),
+        Some(Commands::Doctor) => doctor(),
+        Some(Commands::Adopt { path, trust_mode }) => adopt_repo(&path, &trust_mode),
+        Some(Commands::Release { repo, tag, asset, output, trust_mode }) => {
+            install_release(&repo, tag.as_deref(), asset.as_deref(), output.as_deref(), &trust_mode)
+        }
+        Some(Commands::Auth { command: AuthCommands::Login { forge, git_credential } }) => {
+            auth_login(&forge, git_credential)
+        }
+        Some(Commands::Auth { command: AuthCommands::Logout { forge } }) => auth_logout(&forge),
+        Some(Commands::Auth { command: AuthCommands::Status }) => auth_status(),
     }
 }
@@ -0,0 +1,52 @@
+use crate::config::GitFetchConfig;
+use crate::git::{self, Transport};
+use crate::security;
+use std::fs;
+use std::path::PathBuf;
+
+/// Where the bare mirror of `url` lives: `~/.gitfetch/cache/<host>/<owner>/<name>.git`
+pub fn mirror_path(url: &str) -> PathBuf {
+    GitFetchConfig::cache_root().join(format!("{}.git", git::repo_id(url)))
+}
+
+/// The mirror only pays off for full clones, shallow and partial ones are small already.
+/// A local remote is its own cache.
+pub fn applies(url: &str, depth: Option<u32>, filter: Option<&str>) -> bool {
+    let local = git::parse_remote(url).is_ok_and(|remote| remote.transport == Transport::File);
+    depth.is_none() && filter.is_none() && !local
+}
+
+/// Network stage: create the mirror of `url` or fetch what's new into it.
+/// Only branches and tags are mirrored, not e.g. GitHub's pull request refs.
+pub fn refresh(url: &str) -> Result<PathBuf, String> {
+    let mirror = mirror_path(url);
+
+    if mirror.join("HEAD").exists() {
+        // Forced refspecs, so a tag moved upstream moves here too instead of failing the fetch
+        let refresh = ["fetch", "--quiet", "--prune", "origin", "+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"];
+        if security::run_sandboxed_fetch(&mirror, url, &refresh).is_ok() {
+            return Ok(mirror);
+        }
+        // A broken mirror is only a cache, start it over
+        println!("⚠️  Cache for {} is unusable, rebuilding it", git::repo_id(url));
+        let _ = fs::remove_dir_all(&mirror);
+    }
+
+    let parent = mirror.parent().expect("Mirror path has no parent");
+    let dir_name = mirror.file_name().unwrap().to_string_lossy().to_string();
+    fs::create_dir_all(parent).map_err(|e| format!("Can't create cache: {}", e))?;
+
+    if let Err(e) = security::run_sandboxed_fetch(parent, url, &["clone", "--bare", "--quiet", url, &dir_name]) {
+        let _ = fs::remove_dir_all(&mirror);
+        return Err(e);
+    }
+    git::set_config(&mirror.join("config"), "remote.origin.fetch", "+refs/heads/*:refs/heads/*")?;
+
+    Ok(mirror)
+}
+
+/// The existing mirror of `url`, if there is one
+pub fn existing(url: &str) -> Option<PathBuf> {
+    Some(mirror_path(url)).filter(|mirror| mirror.join("HEAD").exists())
+}
+
@@ -1,7 +1,10 @@
+use crate::git::{self, RemoteUrl, Transport};
+use crate::submodules;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
 
 /// Check if bubblewrap is installed
 pub fn check_bubblewrap() -> Result<(), String> {
@@ -27,60 +30,250 @@ pub fn check_bubblewrap() -> Result<(), String> {
 
 /// Run git command in a sandboxed environment using bubblewrap
 pub fn run_sandboxed_git(workspace: &Path, args: &[&str], with_network: bool) -> Result<(), String> {
+    let status = sandboxed_git_command(workspace, args, with_network)
+        .status()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Git command failed in sandbox".to_string())
+    }
+}
+
+/// Network stage against `remote_url`. A file:// remote's directory is mounted
+/// read-only at its own path so the URL works unchanged inside the sandbox.
+pub fn run_sandboxed_fetch(workspace: &Path, remote_url: &str, args: &[&str]) -> Result<(), String> {
+    let status = sandboxed_fetch_command(workspace, remote_url, args)
+        .status()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Git command failed in sandbox".to_string())
+    }
+}
+
+/// Like `run_sandboxed_fetch`, capturing stdout
+pub fn sandboxed_fetch_output(workspace: &Path, remote_url: &str, args: &[&str]) -> Result<String, String> {
+    let output = sandboxed_fetch_command(workspace, remote_url, args)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err("Git command failed in sandbox".to_string())
+    }
+}
+
+/// Where `run_sandboxed_git_with_mirror` mounts the object cache
+pub const MIRROR_MOUNT: &str = "/mirror";
+
+/// Run git command in the sandbox with a local mirror mounted read-only at `MIRROR_MOUNT`
+pub fn run_sandboxed_git_with_mirror(workspace: &Path, mirror: &Path, args: &[&str], with_network: bool) -> Result<(), String> {
+    let status = sandbox_command(workspace, with_network)
+        .args(["--ro-bind", mirror.to_str().unwrap(), MIRROR_MOUNT])
+        .arg("git")
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if status.success() {
+        Ok(())
+    } else {
+        Err("Git command failed in sandbox".to_string())
+    }
+}
+
+/// Where `run_sandboxed_self` mounts the gitfetch binary
+const SELF_MOUNT: &str = "/run/gitfetch/gitfetch";
+
+/// Run gitfetch itself in the sandbox, for network work git can't do (LFS downloads)
+pub fn run_sandboxed_self(workspace: &Path, args: &[&str], with_network: bool) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Can't find the gitfetch binary: {}", e))?;
+    let status = sandbox_command(workspace, with_network)
+        .arg("--ro-bind").arg(exe).arg(SELF_MOUNT)
+        .arg(SELF_MOUNT)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("gitfetch {} failed in sandbox", args.first().unwrap_or(&"")))
+    }
+}
+
+/// Run git command in the sandbox and capture its stdout
+pub fn sandboxed_git_output(workspace: &Path, args: &[&str], with_network: bool) -> Result<String, String> {
+    let output = sandboxed_git_command(workspace, args, with_network)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err("Git command failed in sandbox".to_string())
+    }
+}
+
+/// Host directories mounted read-only inside the sandbox
+pub const SANDBOX_RO_BINDS: [&str; 6] = ["/usr", "/lib", "/lib64", "/bin", "/sbin", "/etc"];
+
+/// Run an arbitrary command in the sandbox and capture its stdout, for self-checks
+pub fn sandboxed_probe(workspace: &Path, command: &[&str], with_network: bool) -> Result<String, String> {
+    let output = sandbox_command(workspace, with_network)
+        .args(command)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!("{} exited with {}", command.join(" "), output.status))
+    }
+}
+
+/// Build the bubblewrap invocation shared by every sandboxed git call
+fn sandboxed_git_command(workspace: &Path, args: &[&str], with_network: bool) -> Command {
+    let mut cmd = sandbox_command(workspace, with_network);
+    cmd.arg("git").args(args);
+    cmd
+}
+
+fn sandboxed_fetch_command(workspace: &Path, remote_url: &str, args: &[&str]) -> Command {
+    let mut cmd = sandbox_command(workspace, true);
+    if let Ok(RemoteUrl { transport: Transport::File, path, .. }) = git::parse_remote(remote_url) {
+        cmd.args(["--ro-bind", &path, &path]);
+    }
+    cmd.arg("git").args(args);
+    cmd
+}
+
+/// Where the network stage sees the user's SSH agent socket and known_hosts
+const SSH_AGENT_MOUNT: &str = "/run/gitfetch/ssh-agent.sock";
+const KNOWN_HOSTS_MOUNT: &str = "/run/gitfetch/known_hosts";
+
+/// Let ssh:// remotes authenticate through the user's agent. Keys never enter the
+/// sandbox, and unknown host keys fail instead of being accepted.
+fn forward_ssh_agent(cmd: &mut Command) {
+    if let Some(socket) = std::env::var_os("SSH_AUTH_SOCK").filter(|socket| Path::new(socket).exists()) {
+        cmd.arg("--ro-bind").arg(socket).arg(SSH_AGENT_MOUNT)
+            .args(["--setenv", "SSH_AUTH_SOCK", SSH_AGENT_MOUNT]);
+    }
+    
+    if let Some(home) = std::env::var_os("HOME") {
+        let known_hosts = Path::new(&home).join(".ssh").join("known_hosts");
+        if known_hosts.is_file() {
+            cmd.arg("--ro-bind").arg(known_hosts).arg(KNOWN_HOSTS_MOUNT);
+        }
+    }
+    
+    cmd.args(["--setenv", "GIT_SSH_COMMAND"])
+        .arg(format!("ssh -o BatchMode=yes -o StrictHostKeyChecking=yes -o UserKnownHostsFile={}", KNOWN_HOSTS_MOUNT));
+}
+
+/// Build the bubblewrap invocation up to, but not including, the command to run
+fn sandbox_command(workspace: &Path, with_network: bool) -> Command {
     let mut cmd = Command::new("timeout");
     cmd.arg("300") // 5 minute timeout
-        .arg("bwrap")
-        // Read-only system mounts
-        .args(&["--ro-bind", "/usr", "/usr"])
-        .args(&["--ro-bind", "/lib", "/lib"])
-        .args(&["--ro-bind", "/lib64", "/lib64"])
-        .args(&["--ro-bind", "/bin", "/bin"])
-        .args(&["--ro-bind", "/sbin", "/sbin"])
-        .args(&["--ro-bind", "/etc", "/etc"])
-        .args(&["--proc", "/proc"])
+        .arg("bwrap");
+    
+    // Read-only system mounts
+    for dir in SANDBOX_RO_BINDS {
+        cmd.args(["--ro-bind", dir, dir]);
+    }
+    
+    cmd.args(["--proc", "/proc"])
         // Minimal /dev (only what git needs)
-        .args(&["--dev-bind", "/dev/null", "/dev/null"])
-        .args(&["--dev-bind", "/dev/zero", "/dev/zero"])
-        .args(&["--dev-bind", "/dev/urandom", "/dev/urandom"])
-        .args(&["--tmpfs", "/tmp"])
+        .args(["--dev-bind", "/dev/null", "/dev/null"])
+        .args(["--dev-bind", "/dev/zero", "/dev/zero"])
+        .args(["--dev-bind", "/dev/urandom", "/dev/urandom"])
+        .args(["--tmpfs", "/tmp"])
         // Workspace
-        .args(&["--bind", workspace.to_str().unwrap(), "/workspace"])
-        .args(&["--chdir", "/workspace"])
+        .args(["--bind", workspace.to_str().unwrap(), "/workspace"])
+        .args(["--chdir", "/workspace"])
         // Isolation
-        .args(&["--unshare-pid"])
-        .args(&["--unshare-uts"])
-        .args(&["--unshare-cgroup"])
-        .args(&["--die-with-parent"])
-        .args(&["--cap-drop", "ALL"]);
+        .args(["--unshare-pid"])
+        .args(["--unshare-uts"])
+        .args(["--unshare-cgroup"])
+        .args(["--die-with-parent"])
+        .args(["--cap-drop", "ALL"]);
     
     // Network isolation for checkout phase
     if !with_network {
-        cmd.args(&["--unshare-net"]);
+        cmd.args(["--unshare-net"]);
     }
     
     // Restricted environment - DISABLE GIT HOOKS
-    cmd.args(&["--clearenv"])
-        .args(&["--setenv", "PATH", "/usr/bin:/bin"])
-        .args(&["--setenv", "HOME", "/workspace"])
-        .args(&["--setenv", "GIT_CONFIG_COUNT", "1"])
-        .args(&["--setenv", "GIT_CONFIG_KEY_0", "core.hooksPath"])
-        .args(&["--setenv", "GIT_CONFIG_VALUE_0", "/dev/null"]);
-    
-    // Git command
-    cmd.arg("git").args(args);
+    cmd.args(["--clearenv"])
+        .args(["--setenv", "PATH", "/usr/bin:/bin"])
+        .args(["--setenv", "HOME", "/workspace"])
+        .args(["--setenv", "GIT_CONFIG_COUNT", "1"])
+        .args(["--setenv", "GIT_CONFIG_KEY_0", "core.hooksPath"])
+        .args(["--setenv", "GIT_CONFIG_VALUE_0", "/dev/null"]);
     
-    let status = cmd.status()
-        .map_err(|e| format!("Failed to execute bubblewrap: {}", e))?;
+    // Only the network stage can reach the SSH agent, never the checkout
+    if with_network {
+        forward_ssh_agent(&mut cmd);
+    }
     
-    if status.success() {
-        Ok(())
-    } else {
-        Err("Git command failed in sandbox".to_string())
+    cmd
+}
+
+/// Scan repository for suspicious code patterns, with `with_submodules` also the submodules gitfetch fetched
+pub fn scan_for_suspicious_patterns(repo_path: &str, with_submodules: bool) -> Vec<String> {
+    let mut warnings = scan_directory(repo_path);
+    
+    if with_submodules {
+        if let Ok(modules) = Path::new(repo_path).join(".git").join("modules").canonicalize() {
+            scan_submodules(Path::new(repo_path), &modules, "", &mut warnings);
+        }
+    }
+    
+    warnings
+}
+
+/// Submodule paths come from the repo, so only follow plain relative ones that lead strictly
+/// deeper and whose git dir lives under the top-level `.git/modules`
+fn scan_submodules(dir: &Path, modules: &Path, prefix: &str, warnings: &mut Vec<String>) {
+    let Ok(canonical_dir) = dir.canonicalize() else { return };
+    
+    for submodule in git::submodule_paths(&dir.to_string_lossy()) {
+        if !submodules::is_safe_relative(&submodule) {
+            continue;
+        }
+        let submodule_path = dir.join(&submodule);
+        let Ok(canonical) = submodule_path.canonicalize() else { continue };
+        if canonical == canonical_dir || !canonical.starts_with(&canonical_dir) {
+            continue;
+        }
+        if !is_fetched_submodule(&submodule_path, modules) {
+            continue;
+        }
+        
+        let name = format!("{}{}", prefix, submodule);
+        for warning in scan_directory(&submodule_path.to_string_lossy()) {
+            warnings.push(format!("{}/{}", name, warning));
+        }
+        scan_submodules(&submodule_path, modules, &format!("{}/", name), warnings);
     }
 }
 
-/// Scan repository for suspicious code patterns
-pub fn scan_for_suspicious_patterns(repo_path: &str) -> Vec<String> {
+/// Whether a submodule checkout's `.git` file points into `modules`, as `checkout_submodules` sets it up
+fn is_fetched_submodule(path: &Path, modules: &Path) -> bool {
+    let Ok(gitfile) = fs::read_to_string(path.join(".git")) else { return false };
+    let Some(git_dir) = gitfile.trim().strip_prefix("gitdir: ") else { return false };
+    path.join(git_dir).canonicalize().is_ok_and(|git_dir| git_dir.starts_with(modules))
+}
+
+fn scan_directory(repo_path: &str) -> Vec<String> {
     let mut warnings = Vec::new();
     let path = Path::new(repo_path);
     
@@ -102,7 +295,7 @@ pub fn scan_for_suspicious_patterns(repo_path: &str) -> Vec<String> {
         ("bitcoin", "crypto-related"),
     ];
     
-    let extensions = vec!["py", "js", "sh", "bash", "rb", "pl", "php", "rs"];
+    let extensions = ["py", "js", "sh", "bash", "rb", "pl", "php", "rs"];
     
     if let Ok(entries) = fs::read_dir(path) {
         for entry in entries.flatten() {
@@ -142,8 +335,113 @@ pub fn scan_for_suspicious_patterns(repo_path: &str) -> Vec<String> {
     warnings
 }
 
+/// Local git config keys that make git run arbitrary programs
+const DANGEROUS_GIT_KEYS: [&str; 13] = [
+    "core.hookspath",
+    "core.alternaterefscommand",
+    "core.fsmonitor",
+    "core.sshcommand",
+    "core.gitproxy",
+    "core.pager",
+    "core.editor",
+    "core.askpass",
+    "sequence.editor",
+    "diff.external",
+    "credential.helper",
+    "gpg.program",
+    "uploadpack.packobjectshook",
+];
+
+/// Strip hooks and program-running config from a clone that gitfetch didn't make.
+/// Hooks are moved aside rather than deleted. Returns a description of each change.
+pub fn sanitize_git_dir(repo_path: &Path) -> Result<Vec<String>, String> {
+    let git_dir = repo_path.join(".git");
+    let config_file = git_dir.join("config");
+    let config_str = config_file.to_str().ok_or("Invalid repository path")?;
+    let mut changes = Vec::new();
+
+    // Read the file directly so include.path can't pull in anything else
+    let output = Command::new("git")
+        .args(["config", "--file", config_str, "--null", "--list"])
+        .output()
+        .map_err(|e| format!("Failed to read git config: {}", e))?;
+
+    // Entries are `key\nvalue`, NUL separated. Subsection names keep their case for --unset-all.
+    let mut keys: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('\n').unwrap_or((entry, ""));
+            is_dangerous_git_key(&key.to_lowercase(), value).then(|| key.to_string())
+        })
+        .collect();
+    keys.dedup();
+
+    for key in keys {
+        let status = Command::new("git")
+            .args(["config", "--file", config_str, "--unset-all", &key])
+            .status()
+            .map_err(|e| format!("Failed to edit git config: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to remove {} from git config", key));
+        }
+        changes.push(format!("removed config {}", key));
+    }
+
+    let hooks_dir = git_dir.join("hooks");
+    let disabled_dir = git_dir.join("hooks.gitfetch-disabled");
+    if let Ok(entries) = fs::read_dir(&hooks_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".sample") {
+                continue;
+            }
+            fs::create_dir_all(&disabled_dir).map_err(|e| format!("Can't create {}: {}", disabled_dir.display(), e))?;
+            fs::rename(entry.path(), disabled_dir.join(&name))
+                .map_err(|e| format!("Can't move hook {}: {}", name, e))?;
+            changes.push(format!("disabled hook {}", name));
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Whether a config entry (lowercase `key`) can make git run a program
+fn is_dangerous_git_key(key: &str, value: &str) -> bool {
+    if DANGEROUS_GIT_KEYS.contains(&key) || key == "include.path" {
+        return true;
+    }
+
+    // Subsection keys such as filter.lfs.smudge or includeif.<cond>.path
+    let (section, rest) = key.split_once('.').unwrap_or((key, ""));
+    let variable = rest.rsplit('.').next().unwrap_or("");
+    matches!(
+        (section, variable),
+        ("filter", "clean" | "smudge" | "process")
+            | ("diff", "textconv" | "command")
+            | ("merge", "driver")
+            | ("includeif", "path")
+            | ("credential", "helper")
+            | ("remote", "uploadpack" | "receivepack")
+            | ("gpg", "program")
+            | ("difftool" | "mergetool", "cmd" | "path")
+            | ("pager", _)
+    ) || (section == "alias" && value.trim_start().starts_with('!'))
+}
+
+/// Check that a path resolves to somewhere strictly inside the given root
+pub fn is_within_root(path: &Path, root: &Path) -> bool {
+    match (path.canonicalize(), root.canonicalize()) {
+        (Ok(path), Ok(root)) => path != root && path.starts_with(&root),
+        _ => false,
+    }
+}
+
+/// Parallel jobs take turns at the terminal
+static PROMPT_LOCK: Mutex<()> = Mutex::new(());
+
 /// Prompt user for yes/no confirmation
 pub fn prompt_user(message: &str) -> bool {
+    let _guard = PROMPT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
     println!("{}", message);
     print!("> ");
     io::stdout().flush().expect("Failed to flush stdout");
@@ -154,3 +452,37 @@ pub fn prompt_user(message: &str) -> bool {
     
     response == "yes" || response == "y"
 }
+
+#[cfg(test)]
+mod tests {
+    use super::is_dangerous_git_key;
+
+    #[test]
+    fn plain_keys() {
+        assert!(is_dangerous_git_key("core.fsmonitor", "true"));
+        assert!(is_dangerous_git_key("core.alternaterefscommand", "evil"));
+        assert!(is_dangerous_git_key("include.path", "../other"));
+        assert!(!is_dangerous_git_key("core.autocrlf", "true"));
+        assert!(!is_dangerous_git_key("user.name", "someone"));
+    }
+
+    #[test]
+    fn subsection_keys() {
+        assert!(is_dangerous_git_key("filter.lfs.smudge", "git-lfs smudge"));
+        assert!(is_dangerous_git_key("remote.origin.uploadpack", "evil"));
+        assert!(is_dangerous_git_key("remote.origin.receivepack", "evil"));
+        assert!(is_dangerous_git_key("gpg.ssh.program", "evil"));
+        assert!(is_dangerous_git_key("difftool.meld.cmd", "evil"));
+        assert!(is_dangerous_git_key("mergetool.kdiff3.path", "evil"));
+        assert!(is_dangerous_git_key("pager.log", "evil"));
+        assert!(!is_dangerous_git_key("remote.origin.url", "https://example.com/repo"));
+        assert!(!is_dangerous_git_key("remote.origin.fetch", "+refs/heads/*:refs/remotes/origin/*"));
+    }
+
+    #[test]
+    fn aliases_running_shell_commands() {
+        assert!(is_dangerous_git_key("alias.st", "!touch /tmp/pwned"));
+        assert!(is_dangerous_git_key("alias.st", "  !sh"));
+        assert!(!is_dangerous_git_key("alias.st", "status --short"));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::config::GitFetchConfig;
+use crate::git::{self, Transport};
+use crate::security;
+use std::fs;
+use std::path::Path;
+
+/// A submodule whose objects have been fetched, ready for the offline checkout.
+/// Paths are relative to the top-level checkout.
+#[derive(Debug, Clone)]
+pub struct Submodule {
+    pub path: String,
+    pub git_dir: String,
+    pub commit: String,
+}
+
+/// Network stage: find the submodules of `commit`, ask about each one according to
+/// the policy and trust mode, and fetch the approved ones into `.git/modules`.
+/// Nothing is checked out here.
+pub fn fetch_submodules(
+    repo_root: &Path,
+    repo_url: &str,
+    commit: &str,
+    policy: &str,
+    trust_mode: &str,
+) -> Result<Vec<Submodule>, String> {
+    let declared = list_declared(repo_root, ".git", commit)?;
+    if declared.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if policy == "none" {
+        println!("Skipping {} submodules (use --submodules to fetch them)", declared.len());
+        return Ok(Vec::new());
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("SUBMODULES (with network)");
+    println!("{}", "=".repeat(60));
+
+    let context = FetchContext {
+        repo_root,
+        config: GitFetchConfig::load(),
+        policy,
+        trust_mode,
+    };
+    let mut fetched = Vec::new();
+    context.fetch_level(repo_url, ".git", "", declared, &mut fetched)?;
+    Ok(fetched)
+}
+
+/// Offline stage: point each fetched submodule's worktree at its git dir and check it out
+pub fn checkout_submodules(repo_root: &Path, submodules: &[Submodule]) -> Result<(), String> {
+    for submodule in submodules {
+        let worktree = repo_root.join(&submodule.path);
+        fs::create_dir_all(&worktree)
+            .map_err(|e| format!("Can't create {}: {}", submodule.path, e))?;
+
+        // Turn the bare clone into a regular submodule git dir. Links are relative
+        // so the checkout still works once it is copied elsewhere.
+        let module_config = repo_root.join(&submodule.git_dir).join("config");
+        git::set_config(&module_config, "core.bare", "false")?;
+        git::set_config(&module_config, "core.worktree", &format!("{}{}", up_levels(&submodule.git_dir), submodule.path))?;
+        
+        let gitfile = format!("gitdir: {}{}\n", up_levels(&submodule.path), submodule.git_dir);
+        fs::write(worktree.join(".git"), gitfile)
+            .map_err(|e| format!("Can't link {}: {}", submodule.path, e))?;
+
+        security::run_sandboxed_git(
+            repo_root,
+            &["-C", &submodule.path, "-c", "advice.detachedHead=false", "checkout", "--force", &submodule.commit],
+            false,
+        )
+        .map_err(|e| format!("Checkout of submodule {} failed: {}", submodule.path, e))?;
+    }
+
+    Ok(())
+}
+
+/// A gitlink with its `.gitmodules` entry, before it is fetched
+struct Declared {
+    name: String,
+    path: String,
+    url: String,
+    commit: String,
+}
+
+struct FetchContext<'a> {
+    repo_root: &'a Path,
+    config: GitFetchConfig,
+    policy: &'a str,
+    trust_mode: &'a str,
+}
+
+/// Gitlinks declared in `.gitmodules` at `commit` of the given git dir
+fn list_declared(repo_root: &Path, git_dir: &str, commit: &str) -> Result<Vec<Declared>, String> {
+    let git_dir_arg = format!("--git-dir={}", git_dir);
+    let has_gitmodules = security::sandboxed_git_output(
+        repo_root,
+        &[&git_dir_arg, "ls-tree", commit, "--", ".gitmodules"],
+        true,
+    )?;
+    if has_gitmodules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let blob = format!("{}:.gitmodules", commit);
+    let entries = security::sandboxed_git_output(
+        repo_root,
+        &[&git_dir_arg, "config", "--blob", &blob, "--get-regexp", r"^submodule\..*\.(path|url)$"],
+        true,
+    )
+    .unwrap_or_default();
+
+    let mut paths = Vec::new();
+    let mut urls = Vec::new();
+    for line in entries.lines() {
+        let Some((key, value)) = line.split_once(' ') else { continue };
+        let Some(key) = key.strip_prefix("submodule.") else { continue };
+        if let Some(name) = key.strip_suffix(".path") {
+            paths.push((name.to_string(), value.to_string()));
+        } else if let Some(name) = key.strip_suffix(".url") {
+            urls.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    let mut declared = Vec::new();
+    for (name, path) in paths {
+        let Some((_, url)) = urls.iter().find(|(url_name, _)| *url_name == name) else { continue };
+
+        // Names and paths come from the repo, keep them from escaping .git/modules or the checkout
+        if !is_safe_relative(&name) || !is_safe_relative(&path) {
+            return Err(format!("Refusing submodule with unsafe name or path: {} ({})", name, path));
+        }
+
+        let gitlink = security::sandboxed_git_output(
+            repo_root,
+            &[&git_dir_arg, "ls-tree", commit, "--", &path],
+            false,
+        )?;
+        let commit = gitlink.split_whitespace()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .find(|pair| pair[0] == "commit")
+            .map(|pair| pair[1].to_string());
+
+        // Stale .gitmodules entries without a gitlink are ignored, like git does
+        if let Some(commit) = commit {
+            declared.push(Declared { name, path, url: url.clone(), commit });
+        }
+    }
+
+    Ok(declared)
+}
+
+impl FetchContext<'_> {
+    fn fetch_level(
+        &self,
+        parent_url: &str,
+        parent_git_dir: &str,
+        parent_path: &str,
+        declared: Vec<Declared>,
+        fetched: &mut Vec<Submodule>,
+    ) -> Result<(), String> {
+        let repo_root = self.repo_root;
+        let config = &self.config;
+
+        for Declared { name, path, url, commit } in declared {
+            let url = resolve_url(parent_url, &url);
+            let path = if parent_path.is_empty() { path } else { format!("{}/{}", parent_path, path) };
+            let git_dir = format!("{}/modules/{}", parent_git_dir, name);
+
+            println!("\n{} -> {} ({:.8})", path, url, commit);
+
+            // A cloned repo must not be able to point git at other repositories on this machine
+            match git::parse_remote(&url) {
+                Ok(remote) if remote.transport == Transport::File => {
+                    println!("⚠️  Skipped, submodules can't use local paths");
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    println!("⚠️  Skipped, {}", e);
+                    continue;
+                }
+            }
+
+            if !config.is_host_allowed(&url) {
+                println!("⚠️  Skipped, {} is not in allowed_hosts", git::repo_host(&url));
+                continue;
+            }
+
+            // prompt: always ask. recursive: ask like a clone would for this trust mode
+            let should_prompt = match (self.policy, self.trust_mode) {
+                ("prompt", _) | (_, "paranoid") => true,
+                (_, "yolo") => false,
+                _ => config.get_checksum(&url).is_none(),
+            };
+            if should_prompt && !security::prompt_user(&format!("Fetch submodule {}? (yes/no)", path)) {
+                println!("Skipped {}", path);
+                continue;
+            }
+
+            if !repo_root.join(&git_dir).exists() {
+                security::run_sandboxed_fetch(repo_root, &url, &["clone", "--bare", "--quiet", &url, &git_dir])
+                    .map_err(|e| format!("Fetching submodule {} failed: {}", path, e))?;
+            }
+
+            let git_dir_arg = format!("--git-dir={}", git_dir);
+            let spec = format!("{}^{{commit}}", commit);
+            let present = security::sandboxed_git_output(
+                repo_root,
+                &[&git_dir_arg, "rev-parse", "--verify", "--quiet", &spec],
+                false,
+            )
+            .is_ok_and(|found| !found.is_empty());
+            if !present {
+                security::run_sandboxed_fetch(repo_root, &url, &[&git_dir_arg, "fetch", "--quiet", "origin", &commit])
+                    .map_err(|_| format!("Submodule {} commit {} does not exist upstream", path, commit))?;
+            }
+
+            let parent_config = repo_root.join(parent_git_dir).join("config");
+            git::set_config(&parent_config, &format!("submodule.{}.url", name), &url)?;
+            git::set_config(&parent_config, &format!("submodule.{}.active", name), "true")?;
+
+            let nested = list_declared(repo_root, &git_dir, &commit)?;
+            fetched.push(Submodule { path: path.clone(), git_dir: git_dir.clone(), commit });
+            self.fetch_level(&url, &git_dir, &path, nested, fetched)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve `./x` and `../x` submodule URLs against the parent's remote, as git does
+fn resolve_url(parent_url: &str, url: &str) -> String {
+    if !url.starts_with("./") && !url.starts_with("../") {
+        return url.to_string();
+    }
+
+    let mut base = parent_url.trim_end_matches('/').to_string();
+    let mut rest = url;
+    loop {
+        if let Some(stripped) = rest.strip_prefix("./") {
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix("../") {
+            rest = stripped;
+            if let Some(index) = base.rfind('/') {
+                base.truncate(index);
+            }
+        } else {
+            break;
+        }
+    }
+
+    format!("{}/{}", base, rest)
+}
+
+pub(crate) fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with('/')
+        && path.split('/').all(|part| !part.is_empty() && part != "." && part != ".." && part != ".git")
+}
+
+/// `../` repeated once per component, to climb from `path` back to the top-level checkout
+fn up_levels(path: &str) -> String {
+    "../".repeat(path.split('/').filter(|part| !part.is_empty()).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_url;
+
+    #[test]
+    fn absolute_urls_are_kept() {
+        assert_eq!(resolve_url("https://github.com/a/b", "https://gitlab.com/c/d"), "https://gitlab.com/c/d");
+        assert_eq!(resolve_url("https://github.com/a/b", "git@github.com:c/d"), "git@github.com:c/d");
+    }
+
+    #[test]
+    fn sibling_repos_resolve_against_the_parent() {
+        assert_eq!(resolve_url("https://github.com/a/b", "../c"), "https://github.com/a/c");
+        assert_eq!(resolve_url("https://github.com/a/b/", "../c"), "https://github.com/a/c");
+    }
+
+    #[test]
+    fn dot_slash_stays_below_the_parent() {
+        assert_eq!(resolve_url("https://github.com/a/b", "./c"), "https://github.com/a/b/c");
+    }
+
+    #[test]
+    fn several_levels_up() {
+        assert_eq!(resolve_url("https://github.com/a/b", "../../other/c"), "https://github.com/other/c");
+        assert_eq!(resolve_url("https://github.com/a/b", "./../c"), "https://github.com/a/c");
+    }
+}
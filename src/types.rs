@@ -3,12 +3,50 @@ use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InstalledRepo {
+    /// Unique `host/owner/name` key, also the workspace subdirectory
+    #[serde(default)]
+    pub id: String,
     pub name: String,
     pub url: String,
     pub path: String,
     pub commit_hash: Option<String>,
     pub verified: bool,
     pub workspace_path: Option<String>,
+    /// Tag, branch or commit the repo was pinned to with --ref
+    #[serde(default)]
+    pub git_ref: Option<String>,
+    /// Previously installed versions kept for `gitfetch rollback`, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<RepoVersion>,
+    /// Shallow clone depth, kept so updates don't deepen the history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+    /// Partial clone filter such as `blob:none`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+    /// Directories checked out in sparse (cone) mode, empty for a full checkout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sparse_paths: Vec<String>,
+    /// Submodule policy the repo was cloned with: `prompt` or `recursive`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodules: Option<String>,
+    /// Per-object LFS download limit in MB, unset when LFS content isn't downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lfs_max_size: Option<u64>,
+    /// Installed from a source tarball, there is no git history to fetch into
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archive: bool,
+    /// Findings from the most recent suspicious pattern scan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_warnings: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RepoVersion {
+    pub commit_hash: String,
+    pub total_hash: String,
+    pub path: String,
+    pub saved_at: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -18,6 +56,9 @@ pub struct RepoChecksum {
     pub file_checksums: HashMap<String, String>,
     pub total_hash: String,
     pub verified_at: String,
+    /// For LFS repos, whether the hashes cover real LFS content or just pointer files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lfs_materialized: Option<bool>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -32,3 +73,157 @@ pub struct GitHubRepo {
 pub struct GitHubSearchResponse {
     pub items: Vec<GitHubRepo>,
 }
+
+#[derive(Deserialize, Debug)]
+pub struct GitLabProject {
+    pub path_with_namespace: String,
+    pub web_url: String,
+    pub description: Option<String>,
+    pub star_count: u32,
+}
+
+/// Gitea and Forgejo share this API
+#[derive(Deserialize, Debug)]
+pub struct GiteaRepo {
+    pub full_name: String,
+    pub html_url: String,
+    pub description: Option<String>,
+    pub stars_count: u32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GiteaSearchResponse {
+    pub data: Vec<GiteaRepo>,
+}
+
+/// Which API a forge profile speaks
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+    #[serde(alias = "forgejo")]
+    Gitea,
+    Sourcehut,
+}
+
+/// A hosting service instance, usable as `<name>:owner/repo`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForgeProfile {
+    pub kind: ForgeKind,
+    /// Web base URL, e.g. `https://gitlab.example.com`, repositories live under it
+    #[serde(alias = "url")]
+    pub web_url: String,
+    /// API base URL, the service's usual location relative to the web URL when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+    /// Host (and port) of clone URLs when it isn't the web host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_host: Option<String>,
+}
+
+/// A forge API response kept on disk, revalidated with its ETag
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CachedResponse {
+    pub url: String,
+    pub etag: String,
+    pub body: String,
+}
+
+/// Where `gitfetch auth login` put a forge's API token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "store", rename_all = "lowercase")]
+pub enum StoredToken {
+    /// In the tokens file itself
+    File { token: String },
+    /// With git's credential helper, under the forge's API URL
+    Git,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitHubRelease {
+    pub tag_name: String,
+    pub assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitHubAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    pub size: u64,
+    /// `sha256:<hex>`, published by GitHub for newer uploads
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+/// A release asset installed with `gitfetch release`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstalledRelease {
+    /// `host/owner/name` of the repo the release belongs to
+    pub id: String,
+    pub tag: String,
+    pub asset: String,
+    pub path: String,
+    pub sha256: String,
+    /// What the digest was checked against: `SHA256SUMS`, `digest`, `registry` or `none`
+    pub verified_by: String,
+    pub installed_at: String,
+}
+
+/// Current lockfile format version written by `gitfetch lock export`
+pub const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockFile {
+    pub version: u32,
+    pub repos: Vec<LockedRepo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockedRepo {
+    pub name: String,
+    pub url: String,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    pub commit: String,
+    pub total_hash: String,
+    /// Submodule policy, the total hash covers submodule files too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodules: Option<String>,
+    /// LFS download limit in MB, the total hash covers LFS content when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lfs_max_size: Option<u64>,
+    /// Sparse checkout paths, the total hash only covers these
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sparse_paths: Vec<String>,
+    /// Fetched as a source tarball rather than cloned
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archive: bool,
+}
+
+/// Declarative list of repos read from `gitfetch.toml`
+#[derive(Deserialize, Debug, Default)]
+pub struct Manifest {
+    #[serde(default)]
+    pub defaults: ManifestDefaults,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<ManifestRepo>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ManifestDefaults {
+    pub trust_mode: Option<String>,
+    /// Directory repos are installed into when they don't set `path`
+    pub install_dir: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ManifestRepo {
+    /// Repository URL or `owner/repo` shorthand
+    pub repo: String,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    pub trust_mode: Option<String>,
+    /// Install location, relative paths are resolved against the manifest
+    pub path: Option<String>,
+}